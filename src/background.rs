@@ -1,9 +1,14 @@
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawParam, Rect, Mesh, Canvas};
 use ggez::glam::Vec2;
+use crate::rng::GameRng;
 
 pub struct Mountain {
     pub pos: Vec2,
+    prev_pos: Vec2,
+    /// Where the foot of the mountain starts; it never rises above this,
+    /// which would leave a gap under it
+    base_y: f32,
     pub mountain_type: i32,
     segment_width: f32,
     increment: f32,
@@ -13,9 +18,9 @@ pub struct Mountain {
 }
 
 impl Mountain {
-    pub fn new(x: f32, y: f32, mountain_type: i32, window_height: f32) -> Self {
-        let segment_width = window_height / 58.0;
-        let increment = 3.0 * window_height / 58.0;
+    pub fn new(x: f32, y: f32, mountain_type: i32, playfield_height: f32) -> Self {
+        let segment_width = playfield_height / 58.0;
+        let increment = 3.0 * playfield_height / 58.0;
         
         // Colors from Processing version
         let (color1, color2) = if mountain_type < 3 {
@@ -28,6 +33,8 @@ impl Mountain {
 
         Mountain {
             pos: Vec2::new(x, y),
+            prev_pos: Vec2::new(x, y),
+            base_y: y,
             mountain_type,
             segment_width,
             increment,
@@ -38,26 +45,35 @@ impl Mountain {
     }
 
     pub fn update(&mut self, speed: f32, ascent: f32) {
-        if self.mountain_type < 3 {
-            self.pos.x -= 0.05 * speed;
-            self.pos.y += 0.01 * ascent;
-        } else {
-            self.pos.x -= 0.25 * speed;
-            self.pos.y += 0.05 * ascent;
-        }
+        self.prev_pos = self.pos;
+        let (scroll, sink) = if self.mountain_type < 3 { (0.05, 0.01) } else { (0.25, 0.05) };
+        self.pos.x -= scroll * speed;
+        self.pos.y = (self.pos.y + sink * ascent).max(self.base_y);
+    }
+
+    /// How far the highest peak rises above the foot.
+    fn height(&self) -> f32 {
+        14.0 * self.increment
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+    /// Whether climbing has sunk the whole mountain below its starting foot,
+    /// and so out of sight.
+    fn has_sunk(&self) -> bool {
+        self.pos.y - self.height() > self.base_y
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, alpha: f32) -> GameResult {
+        let pos = self.prev_pos.lerp(self.pos, alpha);
         match self.mountain_type {
-            0 | 3 => self.draw_mountain_a(ctx, canvas)?,
-            1 | 4 => self.draw_mountain_b(ctx, canvas)?,
-            2 | 5 => self.draw_mountain_c(ctx, canvas)?,
+            0 | 3 => self.draw_mountain_a(ctx, canvas, pos)?,
+            1 | 4 => self.draw_mountain_b(ctx, canvas, pos)?,
+            2 | 5 => self.draw_mountain_c(ctx, canvas, pos)?,
             _ => {},
         }
         Ok(())
     }
 
-    fn draw_mountain_a(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+    fn draw_mountain_a(&self, ctx: &mut Context, canvas: &mut Canvas, pos: Vec2) -> GameResult {
         // First layer
         for i in 0..29 {
             let height = if i <= 14 {
@@ -67,8 +83,8 @@ impl Mountain {
             };
 
             let rect = Rect::new(
-                self.pos.x + i as f32 * self.segment_width,
-                self.pos.y - height,
+                pos.x + i as f32 * self.segment_width,
+                pos.y - height,
                 self.segment_width,
                 height
             );
@@ -85,8 +101,8 @@ impl Mountain {
             };
 
             let rect = Rect::new(
-                self.pos.x + (i as f32 + 1.0) * self.segment_width,
-                self.pos.y - height,
+                pos.x + (i as f32 + 1.0) * self.segment_width,
+                pos.y - height,
                 self.segment_width,
                 height
             );
@@ -96,7 +112,7 @@ impl Mountain {
         Ok(())
     }
 
-    fn draw_mountain_b(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+    fn draw_mountain_b(&self, ctx: &mut Context, canvas: &mut Canvas, pos: Vec2) -> GameResult {
         // First layer
         for i in 0..41 {
             let height = if i <= 14 {
@@ -108,8 +124,8 @@ impl Mountain {
             };
 
             let rect = Rect::new(
-                self.pos.x + i as f32 * self.segment_width,
-                self.pos.y - height,
+                pos.x + i as f32 * self.segment_width,
+                pos.y - height,
                 self.segment_width,
                 height
             );
@@ -128,8 +144,8 @@ impl Mountain {
             };
 
             let rect = Rect::new(
-                self.pos.x + (i as f32 + 1.0) * self.segment_width,
-                self.pos.y - height,
+                pos.x + (i as f32 + 1.0) * self.segment_width,
+                pos.y - height,
                 self.segment_width,
                 height
             );
@@ -139,20 +155,20 @@ impl Mountain {
         Ok(())
     }
 
-    fn draw_mountain_c(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+    fn draw_mountain_c(&self, ctx: &mut Context, canvas: &mut Canvas, pos: Vec2) -> GameResult {
         // First peak
-        self.draw_mountain_a(ctx, canvas)?;
+        self.draw_mountain_a(ctx, canvas, pos)?;
 
         // Second peak (offset)
         let offset_x = 14.0 * self.segment_width;
         let offset_y = 3.0 * self.increment;
         let second_peak = Mountain::new(
-            self.pos.x + offset_x,
-            self.pos.y + offset_y,
+            pos.x + offset_x,
+            pos.y + offset_y,
             0,
             self.increment * 58.0 / 3.0
         );
-        second_peak.draw_mountain_a(ctx, canvas)?;
+        second_peak.draw_mountain_a(ctx, canvas, second_peak.pos)?;
 
         Ok(())
     }
@@ -160,56 +176,98 @@ impl Mountain {
 
 pub struct Background {
     mountains: Vec<Mountain>,
-    window_width: f32,
-    window_height: f32,
+    playfield_width: f32,
 }
 
 impl Background {
-    pub fn new(window_width: f32, window_height: f32) -> Self {
+    pub fn new(playfield_width: f32, playfield_height: f32, rng: &mut GameRng) -> Self {
         let mut mountains = Vec::new();
         
         // Create far mountains (types 0-2)
         for i in 0..4 {
             mountains.push(Mountain::new(
-                i as f32 * 200.0 + rand::random::<f32>() * 200.0,
-                window_height,
-                rand::random::<i32>() % 3,
-                window_height
+                i as f32 * 200.0 + rng.gen_f32() * 200.0,
+                playfield_height,
+                rng.gen_range(0..3),
+                playfield_height
             ));
         }
         
         // Create near mountains (types 3-5)
         for i in 4..7 {
             mountains.push(Mountain::new(
-                i as f32 * 200.0 + rand::random::<f32>() * 200.0,
-                window_height,
-                3 + rand::random::<i32>() % 3,
-                window_height
+                i as f32 * 200.0 + rng.gen_f32() * 200.0,
+                playfield_height,
+                3 + rng.gen_range(0..3),
+                playfield_height
             ));
         }
 
         Background {
             mountains,
-            window_width,
-            window_height,
+            playfield_width,
         }
     }
 
-    pub fn update(&mut self, speed_x: f32, ascent_speed: f32) {
+    pub fn update(&mut self, speed_x: f32, ascent_speed: f32, rng: &mut GameRng) {
         for mountain in &mut self.mountains {
             mountain.update(speed_x, ascent_speed);
             
-            // Wrap mountains around when they go off screen
-            if mountain.pos.x < -mountain.max_width {
-                mountain.pos.x = self.window_width + rand::random::<f32>() * 50.0;
+            // Wrap mountains around when they go off screen, to the left or
+            // below after a long climb, and bring them back at their full height
+            if mountain.pos.x < -mountain.max_width || mountain.has_sunk() {
+                mountain.pos = Vec2::new(self.playfield_width + rng.gen_f32() * 50.0, mountain.base_y);
+                mountain.prev_pos = mountain.pos;
             }
         }
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, alpha: f32) -> GameResult {
         for mountain in &self.mountains {
-            mountain.draw(ctx, canvas)?;
+            mountain.draw(ctx, canvas, alpha)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mountains_never_leave_a_gap_under_them() {
+        let height = 640.0;
+        let mut rng = GameRng::new(1);
+        let mut background = Background::new(640.0, height, &mut rng);
+        for ascent in [5.0, -5.0, 5.0] {
+            for _ in 0..10_000 {
+                background.update(3.0, ascent, &mut rng);
+                for mountain in &background.mountains {
+                    assert!(mountain.pos.y >= height, "gap under a mountain at {}", mountain.pos.y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mountains_keep_sinking_however_far_the_view_climbs() {
+        let height = 640.0;
+        let mut rng = GameRng::new(2);
+        let mut background = Background::new(640.0, height, &mut rng);
+        let heights = |background: &Background| background.mountains.iter().map(|m| m.pos.y).collect::<Vec<_>>();
+        let mut recycled = 0;
+        for _ in 0..100_000 {
+            let before = heights(&background);
+            background.update(0.0, 5.0, &mut rng);
+            for (mountain, y) in background.mountains.iter().zip(before) {
+                if mountain.pos.y > y {
+                    continue;
+                }
+                // Only a mountain sinking out of sight comes back up
+                assert!(y - mountain.height() > height - 5.0, "stuck at {}", y);
+                recycled += 1;
+            }
+        }
+        assert!(recycled > 0);
+    }
+}
@@ -1,74 +1,290 @@
 use ggez::{Context, GameResult};
-use ggez::graphics::{self, Color, DrawParam, Rect, Mesh, Canvas};
+use ggez::graphics::{self, Color, DrawParam, Mesh, Canvas};
 use ggez::glam::Vec2;
-use rand::Rng;
+use crate::collision::{self, Collider, Shape};
+use crate::rng::GameRng;
+use crate::terrain::Terrain;
+use crate::tuning::{BoulderTuning, Tuning};
 
+// Corners of the rock's outline
+const OUTLINE_POINTS: usize = 9;
+// Times the rock is pushed out of the ground after each move
+const CONTACT_PASSES: usize = 3;
+
+/// A rock that falls, lands on the terrain and rolls down it. It collides
+/// as a circle; the outline it's drawn with is a little lumpier.
+///
+/// Velocity is relative to the ground, so a boulder at rest still scrolls
+/// past with the terrain.
 pub struct Boulder {
+    /// The centre of the rock
     pub pos: Vec2,
-    pub width: f32,
-    pub height: f32,
+    prev_pos: Vec2,
+    pub radius: f32,
+    /// In px/s
+    pub velocity: Vec2,
+    /// In radians, clockwise
+    angle: f32,
+    prev_angle: f32,
+    /// In radians per second
+    angular_velocity: f32,
+    /// Distance of each outline corner from the centre, as a fraction of the radius
+    outline: [f32; OUTLINE_POINTS],
     pub color: Color,
+    /// Set once the truck has scored a near miss on this boulder
+    pub near_missed: bool,
 }
 
 impl Boulder {
-    pub fn new(x: f32, y: f32, window_width: f32) -> Self {
-        let mut rng = rand::thread_rng();
-        let width = rng.gen_range(window_width/20.0..6.0*window_width/20.0);
-        let height = rng.gen_range(window_width/20.0..6.0*window_width/20.0);
-
-        Boulder {
+    pub fn new(x: f32, y: f32, tuning: &BoulderTuning, rng: &mut GameRng) -> Self {
+        let mut boulder = Boulder {
             pos: Vec2::new(x, y),
-            width,
-            height,
+            prev_pos: Vec2::new(x, y),
+            radius: 0.0,
+            velocity: Vec2::ZERO,
+            angle: 0.0,
+            prev_angle: 0.0,
+            angular_velocity: 0.0,
+            outline: [1.0; OUTLINE_POINTS],
             color: Color::from_rgb(100, 100, 100),
+            near_missed: false,
+        };
+        boulder.reshape(tuning, rng);
+        boulder
+    }
+
+    /// Picks a new size, outline and starting roll.
+    fn reshape(&mut self, tuning: &BoulderTuning, rng: &mut GameRng) {
+        self.radius = rng.gen_range(tuning.min_size..tuning.max_size) / 2.0;
+        for corner in &mut self.outline {
+            *corner = rng.gen_range(0.8..1.0);
         }
+        self.velocity = Vec2::new(-rng.gen_range(0.0..=tuning.spawn_roll_speed), 0.0);
+        self.angular_velocity = 0.0;
     }
 
-    pub fn update(&mut self, speed_x: f32, ascent: f32) {
+    /// Advances the boulder by one step of `dt` seconds: it scrolls with the
+    /// world by `speed_x`, falls, and rolls over `terrain`, which has
+    /// already scrolled this step.
+    ///
+    /// The rock collides with the ground as a circle against its slopes, bar
+    /// tops and step faces, so a step taller than it can stop it. It moves in
+    /// pieces no longer than half its radius, so it can't skip over a bar.
+    pub fn update(&mut self, dt: f32, speed_x: f32, tuning: &Tuning, terrain: &Terrain) {
+        self.prev_pos = self.pos;
+        self.prev_angle = self.angle;
+
+        // Gravity is given as a change in velocity per tick, like the truck's
+        self.velocity.y += tuning.physics.gravity;
+        self.angle += self.angular_velocity * dt;
+
+        // Start from where the rock was on the ground that has since scrolled
         self.pos.x -= speed_x;
-        self.pos.y += ascent;
-    }
-
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
-        let boulder_rect = Rect::new(
-            self.pos.x,
-            self.pos.y,
-            self.width,
-            self.height
-        );
-
-        let mesh = Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            boulder_rect,
-            self.color,
-        )?;
-        canvas.draw(&mesh, DrawParam::default());
+        let tuning = &tuning.boulders;
+        let pieces = ((self.velocity.length() * dt) / (self.radius / 2.0)).ceil().max(1.0);
+        let piece_dt = dt / pieces;
+        let mut ground_normal = None;
+        for _ in 0..pieces as u32 {
+            self.pos += self.velocity * piece_dt;
+            // A few passes settle a rock wedged between two surfaces
+            for _ in 0..CONTACT_PASSES {
+                let Some(contact) = terrain.circle_contact(self.pos, self.radius) else {
+                    break;
+                };
+                self.pos += contact.normal * contact.depth;
+                let into_ground = self.velocity.dot(contact.normal);
+                if into_ground < 0.0 {
+                    self.velocity -= (1.0 + tuning.restitution) * into_ground * contact.normal;
+                }
+                ground_normal = Some(contact.normal);
+            }
+        }
+
+        // Roll along whatever it last touched
+        if let Some(normal) = ground_normal {
+            let along = Vec2::new(-normal.y, normal.x);  // Forwards along the ground
+            let rolling_speed = self.velocity.dot(along);
+            self.velocity -= along * rolling_speed * (tuning.rolling_drag * dt).min(1.0);
+            self.angular_velocity = self.velocity.dot(along) / self.radius;
+        }
+    }
+
+    /// Pushes two overlapping boulders apart and bounces them off each
+    /// other, the bigger rock moving less.
+    pub fn collide(&mut self, other: &mut Boulder, restitution: f32) {
+        let (Some(a), Some(b)) = (self.shapes().first().copied(), other.shapes().first().copied()) else {
+            return;
+        };
+        let Some(contact) = collision::intersect(&a, &b) else {
+            return;
+        };
+        let (mass, other_mass) = (self.mass(), other.mass());
+        let total = mass + other_mass;
+        self.pos -= contact.normal * contact.depth * other_mass / total;
+        other.pos += contact.normal * contact.depth * mass / total;
+
+        let closing = (other.velocity - self.velocity).dot(contact.normal);
+        if closing < 0.0 {
+            let impulse = -(1.0 + restitution) * closing / (1.0 / mass + 1.0 / other_mass);
+            self.velocity -= contact.normal * impulse / mass;
+            other.velocity += contact.normal * impulse / other_mass;
+        }
+    }
+
+    fn mass(&self) -> f32 {
+        self.radius * self.radius
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, alpha: f32) -> GameResult {
+        let points: Vec<Vec2> = self.outline.iter()
+            .enumerate()
+            .map(|(i, &corner)| {
+                let turn = i as f32 / OUTLINE_POINTS as f32 * std::f32::consts::TAU;
+                Vec2::from_angle(turn) * corner * self.radius
+            })
+            .collect();
+        let mesh = Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &points, self.color)?;
+        canvas.draw(&mesh, DrawParam::default()
+            .dest(self.prev_pos.lerp(self.pos, alpha))
+            .rotation(self.prev_angle + (self.angle - self.prev_angle) * alpha));
         Ok(())
     }
 
-    pub fn reposition(&mut self, window_width: f32, window_height: f32) {
-        let mut rng = rand::thread_rng();
-        self.pos.x = window_width;
-        self.pos.y = rng.gen_range(-10.0..3.0*window_height/4.0);
-        self.width = rng.gen_range(window_width/20.0..6.0*window_width/20.0);
-        self.height = rng.gen_range(window_width/20.0..6.0*window_width/20.0);
-    }
-
-    pub fn collides_with_player(&self, player_pos: Vec2, player_width: f32, player_height: f32) -> bool {
-        let player_left = player_pos.x;
-        let player_right = player_pos.x + player_width;
-        let player_top = player_pos.y - player_height;
-        let player_bottom = player_pos.y;
-        
-        let boulder_left = self.pos.x;
-        let boulder_right = self.pos.x + self.width;
-        let boulder_top = self.pos.y;
-        let boulder_bottom = self.pos.y + self.height;
-
-        player_left < boulder_right && 
-        player_right > boulder_left &&
-        player_top < boulder_bottom && 
-        player_bottom > boulder_top
+    /// Whether the rock is wholly outside the view whose top is at world y
+    /// `view_top`: off either side or below the bottom. Rocks still falling
+    /// in from above count as in view.
+    pub fn has_left_view(&self, playfield_width: f32, view_top: f32, playfield_height: f32) -> bool {
+        self.pos.x + self.radius < 0.0
+            || self.pos.x - self.radius > playfield_width
+            || self.pos.y - self.radius > view_top + playfield_height
+    }
+
+    /// Drops a new rock in from past the right edge, near the top of the
+    /// view whose top is at world y `view_top`.
+    pub fn reposition(&mut self, playfield_width: f32, view_top: f32, playfield_height: f32, tuning: &BoulderTuning, rng: &mut GameRng) {
+        self.reshape(tuning, rng);
+        self.pos.x = playfield_width + self.radius;
+        self.pos.y = view_top + rng.gen_range(-10.0..playfield_height/3.0);
+        self.prev_pos = self.pos;
+        self.near_missed = false;
+    }
+}
+
+impl Collider for Boulder {
+    fn shapes(&self) -> Vec<Shape> {
+        vec![Shape::Circle {
+            center: self.pos,
+            radius: self.radius,
+        }]
+    }
+
+    fn motion(&self) -> Vec2 {
+        self.pos - self.prev_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::{Brush, BrushMode, Falloff, Stroke, TerrainStyle};
+    use crate::TICK_DT;
+
+    const BAR_WIDTH: f32 = 32.0;
+
+    fn boulder(x: f32, y: f32, radius: f32, velocity: Vec2) -> Boulder {
+        let mut rng = GameRng::new(1);
+        let mut boulder = Boulder::new(x, y, &Tuning::default().boulders, &mut rng);
+        boulder.radius = radius;
+        boulder.velocity = velocity;
+        boulder
+    }
+
+    /// Ground whose `i`th bar from the left is at `height(i)`, shaped with
+    /// the mouse a bar at a time.
+    fn terrain(style: TerrainStyle, height: impl Fn(usize) -> f32) -> Terrain {
+        let mut terrain = Terrain::new(640.0, 500.0, style);
+        for i in 0..22 {
+            let brush = Brush {
+                radius: BAR_WIDTH / 2.0,
+                rate: f32::INFINITY,
+                falloff: Falloff::Constant,
+            };
+            let stroke = Stroke {
+                pos: Vec2::new(i as f32 * BAR_WIDTH, height(i)),
+                mode: BrushMode::Flatten,
+                top: 0.0,
+                bottom: 640.0,
+            };
+            terrain.update(&stroke, &brush, 0.0, TICK_DT, f32::INFINITY);
+        }
+        terrain
+    }
+
+    fn run(boulder: &mut Boulder, terrain: &Terrain, seconds: f32) {
+        let tuning = Tuning::default();
+        for _ in 0..(seconds / TICK_DT) as usize {
+            boulder.update(TICK_DT, 0.0, &tuning, terrain);
+        }
+    }
+
+    #[test]
+    fn boulders_roll_down_every_style_of_hill() {
+        for style in TerrainStyle::ALL {
+            // Downhill to the left, 15px a bar
+            let terrain = terrain(style, |i| 200.0 + (21 - i) as f32 * 15.0);
+            let start = 15.0 * BAR_WIDTH;
+            let mut rock = boulder(start, terrain.height_at(start) - 30.0, 24.0, Vec2::ZERO);
+            run(&mut rock, &terrain, 1.5);
+            assert!(rock.pos.x < start - 4.0 * BAR_WIDTH, "{:?}: only got to {}", style, rock.pos.x);
+        }
+    }
+
+    #[test]
+    fn boulders_rest_on_flat_ground() {
+        for style in TerrainStyle::ALL {
+            let terrain = terrain(style, |_| 500.0);
+            let mut rock = boulder(320.0, 400.0, 24.0, Vec2::ZERO);
+            run(&mut rock, &terrain, 3.0);
+            assert!((rock.pos.x - 320.0).abs() < 1.0, "{:?}: drifted to {}", style, rock.pos.x);
+            assert!((rock.pos.y - (500.0 - 24.0)).abs() < 1.0, "{:?}: resting at {}", style, rock.pos.y);
+        }
+    }
+
+    #[test]
+    fn a_step_taller_than_the_rock_turns_it_back() {
+        // A wall three bars wide, 150px high, in the way of a rock rolling left
+        let terrain = terrain(TerrainStyle::Blocky, |i| if (8..=10).contains(&i) { 350.0 } else { 500.0 });
+        let mut rock = boulder(15.0 * BAR_WIDTH, 500.0 - 20.0, 20.0, Vec2::new(-400.0, 0.0));
+        run(&mut rock, &terrain, 2.0);
+        assert!(rock.pos.x > 10.5 * BAR_WIDTH + 20.0 - 1.0, "went through or over the wall to {}", rock.pos.x);
+        assert!(rock.pos.y > 400.0, "climbed onto the wall at {}", rock.pos.y);
+    }
+
+    #[test]
+    fn big_boulders_do_not_sink_into_hillsides() {
+        for style in TerrainStyle::ALL {
+            // A valley: a big rock rests against both sides of it
+            let terrain = terrain(style, |i| 500.0 - (i as f32 - 10.0).abs() * 25.0);
+            let mut rock = boulder(10.0 * BAR_WIDTH, 100.0, 96.0, Vec2::ZERO);
+            run(&mut rock, &terrain, 3.0);
+            let overlap = terrain.circle_contact(rock.pos, rock.radius).map_or(0.0, |c| c.depth);
+            assert!(overlap < 2.0, "{:?}: sunk {}px into the ground", style, overlap);
+            assert!(rock.pos.y + rock.radius < 500.0 - 10.0, "{:?}: reached the valley floor", style);
+        }
+    }
+
+    #[test]
+    fn rocks_leave_the_view_past_either_side_or_the_bottom() {
+        let view_top = -1000.0;
+        let inside = boulder(320.0, view_top + 300.0, 20.0, Vec2::ZERO);
+        assert!(!inside.has_left_view(640.0, view_top, 640.0));
+        // Just spawned past the right edge, or still falling in from above
+        assert!(!boulder(640.0 + 20.0, view_top + 100.0, 20.0, Vec2::ZERO).has_left_view(640.0, view_top, 640.0));
+        assert!(!boulder(320.0, view_top - 200.0, 20.0, Vec2::ZERO).has_left_view(640.0, view_top, 640.0));
+
+        for (x, y) in [(-21.0, 300.0), (661.0, 300.0), (320.0, 661.0)] {
+            let rock = boulder(x, view_top + y, 20.0, Vec2::ZERO);
+            assert!(rock.has_left_view(640.0, view_top, 640.0), "({}, {})", x, y);
+        }
     }
 }
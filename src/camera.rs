@@ -0,0 +1,129 @@
+use ggez::graphics::Rect;
+use ggez::glam::Vec2;
+
+use crate::PLAYFIELD_HEIGHT;
+
+// The band of the screen the truck is kept inside, from the top
+const BAND_TOP: f32 = PLAYFIELD_HEIGHT / 2.0;
+const BAND_BOTTOM: f32 = PLAYFIELD_HEIGHT * 5.0 / 6.0;
+// Share of the way to the band the camera moves each step
+const FOLLOW_RATE: f32 = 0.1;
+
+/// Picks which part of the world is on screen.
+///
+/// The simulation runs in world coordinates, which match playfield
+/// coordinates except that y keeps going up (more and more negative) as
+/// the truck climbs. The camera only moves vertically: the world already
+/// scrolls past the truck horizontally. Screen coordinates are playfield
+/// coordinates of what's visible, so the top of the screen is world y `top`.
+pub struct Camera {
+    top: f32,
+    prev_top: f32,
+}
+
+impl Camera {
+    pub fn new(top: f32) -> Self {
+        Camera {
+            top,
+            prev_top: top,
+        }
+    }
+
+    /// Eases the view towards keeping world y `target_y` inside the band,
+    /// so small hops don't shake the screen but climbs and drops are followed.
+    /// However fast it moves, the target never leaves the screen.
+    pub fn follow(&mut self, target_y: f32) {
+        self.prev_top = self.top;
+        let on_screen = target_y - self.top;
+        let goal = if on_screen < BAND_TOP {
+            target_y - BAND_TOP
+        } else if on_screen > BAND_BOTTOM {
+            target_y - BAND_BOTTOM
+        } else {
+            self.top
+        };
+        self.top += (goal - self.top) * FOLLOW_RATE;
+        self.top = self.top.clamp(target_y - PLAYFIELD_HEIGHT, target_y);
+    }
+
+    /// World y of the top of the screen.
+    pub fn top(&self) -> f32 {
+        self.top
+    }
+
+    /// How far the view rose during the last step.
+    pub fn ascent(&self) -> f32 {
+        self.prev_top - self.top
+    }
+
+    pub fn to_world(&self, screen_pos: Vec2) -> Vec2 {
+        screen_pos + Vec2::new(0.0, self.top)
+    }
+
+    /// `screen` moved to where the camera is looking, `alpha` of the way
+    /// through the current step; suitable for `Canvas::set_screen_coordinates`
+    /// when drawing in world coordinates.
+    pub fn view(&self, screen: Rect, alpha: f32) -> Rect {
+        let top = self.prev_top + (self.top - self.prev_top) * alpha;
+        Rect::new(screen.x, screen.y + top, screen.w, screen.h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_hops_inside_the_band_leave_the_view_alone() {
+        let mut camera = Camera::new(0.0);
+        for y in [BAND_TOP, BAND_BOTTOM, (BAND_TOP + BAND_BOTTOM) / 2.0] {
+            camera.follow(y);
+            assert_eq!(camera.top(), 0.0);
+            assert_eq!(camera.ascent(), 0.0);
+        }
+    }
+
+    #[test]
+    fn climbs_are_eased_into_the_band() {
+        let mut camera = Camera::new(0.0);
+        let target = -1000.0;
+        camera.follow(target);
+        let first_step = camera.ascent();
+        assert!(first_step > 0.0);
+        for _ in 0..200 {
+            camera.follow(target);
+            assert!(camera.ascent() <= first_step);
+        }
+        assert!((target - camera.top() - BAND_TOP).abs() < 1.0);
+    }
+
+    #[test]
+    fn a_fast_drop_stays_on_screen() {
+        let mut camera = Camera::new(0.0);
+        let mut y = BAND_BOTTOM;
+        for _ in 0..100 {
+            y += 50.0;
+            camera.follow(y);
+            let on_screen = y - camera.top();
+            assert!((0.0..=PLAYFIELD_HEIGHT).contains(&on_screen), "{}", on_screen);
+        }
+    }
+
+    #[test]
+    fn screen_positions_map_to_the_world_below_the_top() {
+        let mut camera = Camera::new(0.0);
+        camera.follow(-300.0);
+        let point = Vec2::new(12.0, 34.0);
+        assert_eq!(camera.to_world(point), Vec2::new(12.0, camera.top() + 34.0));
+    }
+
+    #[test]
+    fn the_view_is_interpolated_between_steps() {
+        let mut camera = Camera::new(0.0);
+        camera.follow(-500.0);
+        let screen = Rect::new(0.0, 0.0, 640.0, PLAYFIELD_HEIGHT);
+        assert_eq!(camera.view(screen, 0.0).y, 0.0);
+        assert_eq!(camera.view(screen, 1.0).y, camera.top());
+        assert_eq!(camera.view(screen, 0.5).y, camera.top() / 2.0);
+    }
+}
@@ -0,0 +1,356 @@
+use ggez::glam::Vec2;
+
+/// A convex collision shape in playfield coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Aabb { min: Vec2, max: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+    /// A box rotated by `rotation` radians around its centre
+    Obb { center: Vec2, half_extents: Vec2, rotation: f32 },
+}
+
+/// How two overlapping shapes touch. `normal` is a unit vector pointing from
+/// the first shape towards the second; moving the second shape by
+/// `normal * depth` separates them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+/// The first moment two moving colliders touched during a step. `time` runs
+/// from 0.0 at the start of the step to 1.0 at its end, and `contact` is
+/// measured at that moment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub time: f32,
+    pub contact: Contact,
+}
+
+/// Anything that takes part in collisions. A collider may be made of several shapes.
+pub trait Collider {
+    /// The shapes at the collider's current position.
+    fn shapes(&self) -> Vec<Shape>;
+
+    /// How far the collider moved during the last step.
+    fn motion(&self) -> Vec2;
+}
+
+/// Shapes closer than this are treated as touching when sweeping.
+const CONTACT_SKIN: f32 = 0.5;
+
+impl Shape {
+    pub fn translated(self, by: Vec2) -> Shape {
+        match self {
+            Shape::Aabb { min, max } => Shape::Aabb { min: min + by, max: max + by },
+            Shape::Circle { center, radius } => Shape::Circle { center: center + by, radius },
+            Shape::Obb { center, half_extents, rotation } => Shape::Obb { center: center + by, half_extents, rotation },
+        }
+    }
+
+    /// Grows the shape by `margin` on every side.
+    pub fn inflated(self, margin: f32) -> Shape {
+        match self {
+            Shape::Aabb { min, max } => Shape::Aabb { min: min - margin, max: max + margin },
+            Shape::Circle { center, radius } => Shape::Circle { center, radius: radius + margin },
+            Shape::Obb { center, half_extents, rotation } => Shape::Obb { center, half_extents: half_extents + margin, rotation },
+        }
+    }
+
+    fn as_box(&self) -> Option<OrientedBox> {
+        match *self {
+            Shape::Aabb { min, max } => Some(OrientedBox {
+                center: (min + max) / 2.0,
+                half_extents: (max - min) / 2.0,
+                axes: [Vec2::X, Vec2::Y],
+            }),
+            Shape::Obb { center, half_extents, rotation } => {
+                let axis = Vec2::from_angle(rotation);
+                Some(OrientedBox {
+                    center,
+                    half_extents,
+                    axes: [axis, axis.perp()],
+                })
+            },
+            Shape::Circle { .. } => None,
+        }
+    }
+}
+
+struct OrientedBox {
+    center: Vec2,
+    half_extents: Vec2,
+    axes: [Vec2; 2],
+}
+
+impl OrientedBox {
+    /// Half the length of the box's shadow on `axis`.
+    fn projected_radius(&self, axis: Vec2) -> f32 {
+        self.half_extents.x * self.axes[0].dot(axis).abs()
+            + self.half_extents.y * self.axes[1].dot(axis).abs()
+    }
+}
+
+/// Returns the contact between two shapes, or `None` if they don't overlap.
+pub fn intersect(a: &Shape, b: &Shape) -> Option<Contact> {
+    match (a, b) {
+        (
+            Shape::Circle { center: ca, radius: ra },
+            Shape::Circle { center: cb, radius: rb },
+        ) => circle_circle(*ca, *ra, *cb, *rb),
+        (_, Shape::Circle { center, radius }) => box_circle(&a.as_box()?, *center, *radius),
+        (Shape::Circle { center, radius }, _) => {
+            box_circle(&b.as_box()?, *center, *radius).map(|c| Contact { normal: -c.normal, ..c })
+        },
+        _ => box_box(&a.as_box()?, &b.as_box()?),
+    }
+}
+
+/// Returns the deepest contact between any shape of `a` and any shape of `b`.
+pub fn deepest_contact(a: &[Shape], b: &[Shape]) -> Option<Contact> {
+    a.iter()
+        .flat_map(|sa| b.iter().filter_map(move |sb| intersect(sa, sb)))
+        .max_by(|x, y| x.depth.total_cmp(&y.depth))
+}
+
+/// Finds when two colliders first touched during their last step, however
+/// far they moved in it.
+pub fn sweep(a: &impl Collider, b: &impl Collider) -> Option<Hit> {
+    sweep_shapes(&a.shapes(), a.motion(), &b.shapes(), b.motion())
+}
+
+/// Sweeps shapes back along their motion from the start of the step by
+/// conservative advancement: each move is no longer than the gap between the
+/// shapes, so they can never pass through each other unnoticed. Shapes are
+/// given at their end-of-step positions.
+pub fn sweep_shapes(a: &[Shape], a_motion: Vec2, b: &[Shape], b_motion: Vec2) -> Option<Hit> {
+    let start_a: Vec<Shape> = a.iter().map(|s| s.translated(-a_motion)).collect();
+    let skinned_a: Vec<Shape> = start_a.iter().map(|s| s.inflated(CONTACT_SKIN)).collect();
+    let start_b: Vec<Shape> = b.iter().map(|s| s.translated(-b_motion)).collect();
+
+    // Work in a's frame of reference, where only b moves
+    let relative = b_motion - a_motion;
+    let travel = relative.length();
+
+    let mut time = 0.0;
+    while time <= 1.0 {
+        let moved_b: Vec<Shape> = start_b.iter().map(|s| s.translated(relative * time)).collect();
+        if let Some(contact) = deepest_contact(&skinned_a, &moved_b) {
+            return Some(Hit { time, contact });
+        }
+        if travel == 0.0 {
+            return None;
+        }
+        time += separation(&start_a, &moved_b).max(CONTACT_SKIN) / travel;
+    }
+    None
+}
+
+/// A lower bound on the distance between the closest pair of shapes.
+fn separation(a: &[Shape], b: &[Shape]) -> f32 {
+    a.iter()
+        .flat_map(|sa| b.iter().map(move |sb| shape_separation(sa, sb)))
+        .fold(f32::INFINITY, f32::min)
+        .max(0.0)
+}
+
+fn shape_separation(a: &Shape, b: &Shape) -> f32 {
+    match (a, b) {
+        (
+            Shape::Circle { center: ca, radius: ra },
+            Shape::Circle { center: cb, radius: rb },
+        ) => ca.distance(*cb) - ra - rb,
+        (box_shape, Shape::Circle { center, radius }) | (Shape::Circle { center, radius }, box_shape) => {
+            box_shape.as_box().map_or(0.0, |b| box_point_distance(&b, *center) - radius)
+        },
+        _ => match (a.as_box(), b.as_box()) {
+            (Some(a), Some(b)) => box_box_separation(&a, &b),
+            _ => 0.0,
+        },
+    }
+}
+
+fn box_point_distance(b: &OrientedBox, point: Vec2) -> f32 {
+    let delta = point - b.center;
+    let local = Vec2::new(delta.dot(b.axes[0]), delta.dot(b.axes[1]));
+    (local.abs() - b.half_extents).max(Vec2::ZERO).length()
+}
+
+/// The widest gap between the boxes' shadows on any separating axis.
+fn box_box_separation(a: &OrientedBox, b: &OrientedBox) -> f32 {
+    let delta = b.center - a.center;
+    a.axes.iter()
+        .chain(b.axes.iter())
+        .map(|axis| delta.dot(*axis).abs() - a.projected_radius(*axis) - b.projected_radius(*axis))
+        .fold(f32::NEG_INFINITY, f32::max)
+}
+
+fn circle_circle(ca: Vec2, ra: f32, cb: Vec2, rb: f32) -> Option<Contact> {
+    let delta = cb - ca;
+    let distance = delta.length();
+    if distance >= ra + rb {
+        return None;
+    }
+    Some(Contact {
+        normal: delta.try_normalize().unwrap_or(Vec2::NEG_Y),
+        depth: ra + rb - distance,
+    })
+}
+
+/// Separating-axis test between two boxes.
+fn box_box(a: &OrientedBox, b: &OrientedBox) -> Option<Contact> {
+    let delta = b.center - a.center;
+    let mut best: Option<Contact> = None;
+    for axis in a.axes.iter().chain(b.axes.iter()) {
+        let distance = delta.dot(*axis);
+        let overlap = a.projected_radius(*axis) + b.projected_radius(*axis) - distance.abs();
+        if overlap <= 0.0 {
+            return None;
+        }
+        if best.is_none_or(|c| overlap < c.depth) {
+            let normal = if distance < 0.0 { -*axis } else { *axis };
+            best = Some(Contact { normal, depth: overlap });
+        }
+    }
+    best
+}
+
+/// Contact from a box to a circle.
+fn box_circle(b: &OrientedBox, center: Vec2, radius: f32) -> Option<Contact> {
+    let delta = center - b.center;
+    let local = Vec2::new(delta.dot(b.axes[0]), delta.dot(b.axes[1]));
+    let clamped = local.clamp(-b.half_extents, b.half_extents);
+
+    if clamped == local {
+        // The centre is inside the box; push out through the nearest face
+        let gap = b.half_extents - local.abs();
+        let (axis, side, face_gap) = if gap.x < gap.y {
+            (b.axes[0], local.x, gap.x)
+        } else {
+            (b.axes[1], local.y, gap.y)
+        };
+        let normal = if side < 0.0 { -axis } else { axis };
+        return Some(Contact { normal, depth: face_gap + radius });
+    }
+
+    let closest = b.center + b.axes[0] * clamped.x + b.axes[1] * clamped.y;
+    let offset = center - closest;
+    let distance = offset.length();
+    if distance >= radius {
+        return None;
+    }
+    Some(Contact {
+        normal: offset / distance,
+        depth: radius - distance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A wall 2px thick and 100px tall, centred on x = 100
+    const WALL: Shape = Shape::Obb {
+        center: Vec2::new(100.0, 0.0),
+        half_extents: Vec2::new(1.0, 50.0),
+        rotation: 0.0,
+    };
+
+    fn ball(x: f32, y: f32) -> Shape {
+        Shape::Circle { center: Vec2::new(x, y), radius: 5.0 }
+    }
+
+    #[test]
+    fn a_fast_circle_does_not_tunnel_through_a_thin_box() {
+        // Moves 200px in one step, far more than the wall and ball together
+        let end = ball(200.0, 0.0);
+        assert_eq!(intersect(&WALL, &end), None);
+
+        let hit = sweep_shapes(&[WALL], Vec2::ZERO, &[end], Vec2::new(200.0, 0.0)).unwrap();
+        // The ball's edge reaches the wall's face, less the skin, 94px in
+        let expected = (100.0 - 1.0 - 5.0 - CONTACT_SKIN) / 200.0;
+        assert!((hit.time - expected).abs() < CONTACT_SKIN / 200.0, "time {}", hit.time);
+        // From the wall towards the ball, which is on its left
+        assert!(hit.contact.normal.abs_diff_eq(Vec2::NEG_X, 1e-4), "normal {}", hit.contact.normal);
+        assert!(hit.contact.depth <= CONTACT_SKIN + 1e-4);
+    }
+
+    #[test]
+    fn sweeping_works_in_either_frame() {
+        // The wall rushing left past a still ball hits it at the same time
+        let moved_wall = WALL.translated(Vec2::new(-200.0, 0.0));
+        let still = ball(0.0, 0.0);
+        let hit = sweep_shapes(&[moved_wall], Vec2::new(-200.0, 0.0), &[still], Vec2::ZERO).unwrap();
+        let expected = (100.0 - 1.0 - 5.0 - CONTACT_SKIN) / 200.0;
+        assert!((hit.time - expected).abs() < CONTACT_SKIN / 200.0, "time {}", hit.time);
+
+        // With the ball first the normal points the other way
+        let hit = sweep_shapes(&[ball(200.0, 0.0)], Vec2::new(200.0, 0.0), &[WALL], Vec2::ZERO).unwrap();
+        assert!(hit.contact.normal.abs_diff_eq(Vec2::X, 1e-4), "normal {}", hit.contact.normal);
+    }
+
+    #[test]
+    fn a_fast_box_does_not_tunnel_through_a_thin_box() {
+        let bullet = Shape::Obb {
+            center: Vec2::new(300.0, 20.0),
+            half_extents: Vec2::new(3.0, 3.0),
+            rotation: 0.3,
+        };
+        let hit = sweep_shapes(&[WALL], Vec2::ZERO, &[bullet], Vec2::new(300.0, 0.0)).unwrap();
+        assert!(hit.time > 0.3 && hit.time < 0.33, "time {}", hit.time);
+        assert!(hit.contact.normal.abs_diff_eq(Vec2::NEG_X, 1e-4), "normal {}", hit.contact.normal);
+    }
+
+    #[test]
+    fn a_circle_passing_the_end_of_a_box_misses() {
+        let end = ball(200.0, 60.0);
+        assert_eq!(sweep_shapes(&[WALL], Vec2::ZERO, &[end], Vec2::new(200.0, 0.0)), None);
+    }
+
+    #[test]
+    fn overlapping_shapes_hit_at_the_start_of_the_step() {
+        let hit = sweep_shapes(&[WALL], Vec2::ZERO, &[ball(103.0, 0.0)], Vec2::ZERO).unwrap();
+        assert_eq!(hit.time, 0.0);
+        assert!(hit.contact.normal.abs_diff_eq(Vec2::X, 1e-4));
+        assert!((hit.contact.depth - (5.0 - 2.0 + CONTACT_SKIN)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn aabbs_move_and_grow() {
+        let aabb = Shape::Aabb { min: Vec2::new(0.0, 0.0), max: Vec2::new(10.0, 20.0) };
+        assert_eq!(
+            aabb.translated(Vec2::new(5.0, -5.0)),
+            Shape::Aabb { min: Vec2::new(5.0, -5.0), max: Vec2::new(15.0, 15.0) }
+        );
+        assert_eq!(
+            aabb.inflated(2.0),
+            Shape::Aabb { min: Vec2::new(-2.0, -2.0), max: Vec2::new(12.0, 22.0) }
+        );
+    }
+
+    #[test]
+    fn aabbs_collide_like_unrotated_boxes() {
+        let aabb = Shape::Aabb { min: Vec2::new(99.0, -50.0), max: Vec2::new(101.0, 50.0) };
+        let circle = ball(104.0, 10.0);
+        assert_eq!(intersect(&aabb, &circle), intersect(&WALL, &circle));
+        let contact = intersect(&aabb, &circle).unwrap();
+        assert!(contact.normal.abs_diff_eq(Vec2::X, 1e-4));
+        assert!((contact.depth - 2.0).abs() < 1e-4);
+
+        // Against another box, pushed out along the shallower axis
+        let other = Shape::Aabb { min: Vec2::new(100.0, 45.0), max: Vec2::new(120.0, 60.0) };
+        let contact = intersect(&aabb, &other).unwrap();
+        assert!(contact.normal.abs_diff_eq(Vec2::X, 1e-4), "normal {}", contact.normal);
+        assert!((contact.depth - 1.0).abs() < 1e-4);
+        assert_eq!(intersect(&aabb, &other.translated(Vec2::new(5.0, 0.0))), None);
+    }
+
+    #[test]
+    fn a_fast_circle_does_not_tunnel_through_a_thin_aabb() {
+        let aabb = Shape::Aabb { min: Vec2::new(99.0, -50.0), max: Vec2::new(101.0, 50.0) };
+        let hit = sweep_shapes(&[aabb], Vec2::ZERO, &[ball(200.0, 0.0)], Vec2::new(200.0, 0.0)).unwrap();
+        let expected = (100.0 - 1.0 - 5.0 - CONTACT_SKIN) / 200.0;
+        assert!((hit.time - expected).abs() < CONTACT_SKIN / 200.0, "time {}", hit.time);
+        assert!(hit.contact.normal.abs_diff_eq(Vec2::NEG_X, 1e-4));
+    }
+}
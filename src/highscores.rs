@@ -0,0 +1,308 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// How many scores the table keeps.
+pub const MAX_ENTRIES: usize = 10;
+/// The longest name a player can enter.
+pub const MAX_NAME_LENGTH: usize = 12;
+
+// Bumped whenever the meaning of a field changes. Added fields get a
+// default instead, so older files still load as they are.
+const VERSION: u32 = 1;
+
+/// One finished run in the table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+    /// The day the run was played, as `YYYY-MM-DD`
+    pub date: String,
+    /// Kept as a string, since TOML integers can't hold every `u64`
+    #[serde(serialize_with = "seed_to_string", deserialize_with = "seed_from_string")]
+    pub seed: u64,
+    /// In metres
+    pub distance: i32,
+    /// Best altitude, in metres
+    pub altitude: i32,
+}
+
+impl Default for HighScore {
+    fn default() -> Self {
+        HighScore {
+            name: "Anonymous".to_string(),
+            score: 0,
+            date: String::new(),
+            seed: 0,
+            distance: 0,
+            altitude: 0,
+        }
+    }
+}
+
+/// The best runs on this machine, highest score first.
+///
+/// Stored as TOML in the user's data directory with a `version` number.
+/// Unknown fields are ignored and missing ones take their default, so a
+/// file written by an older or newer build of the same version loads fine;
+/// a file from a later version is refused rather than overwritten.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    entries: Vec<HighScore>,
+}
+
+#[derive(Debug)]
+pub enum HighScoreError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for HighScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HighScoreError::Io(e) => write!(f, "could not access high score file: {}", e),
+            HighScoreError::Parse(e) => write!(f, "could not parse high score file: {}", e),
+            HighScoreError::Serialize(e) => write!(f, "could not write high scores: {}", e),
+            HighScoreError::UnsupportedVersion(version) => {
+                write!(f, "high score file version {} is newer than this game", version)
+            },
+        }
+    }
+}
+
+impl std::error::Error for HighScoreError {}
+
+impl HighScores {
+    /// Reads the table from `path`. A missing file is an empty table.
+    pub fn load(path: &Path) -> Result<HighScores, HighScoreError> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HighScores::default()),
+            Err(e) => return Err(HighScoreError::Io(e)),
+        };
+        let mut table: HighScores = toml::from_str(&source).map_err(HighScoreError::Parse)?;
+        if table.version > VERSION {
+            return Err(HighScoreError::UnsupportedVersion(table.version));
+        }
+
+        table.version = VERSION;
+        table.entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        table.entries.truncate(MAX_ENTRIES);
+        Ok(table)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), HighScoreError> {
+        let table = HighScores {
+            version: VERSION,
+            entries: self.entries.clone(),
+        };
+        let source = toml::to_string(&table).map_err(HighScoreError::Serialize)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(HighScoreError::Io)?;
+        }
+        std::fs::write(path, source).map_err(HighScoreError::Io)
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    /// Whether a run scoring `score` would make it into the table.
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0 && (self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|e| score > e.score))
+    }
+
+    /// Adds `entry` below any equal scores already in the table, dropping the
+    /// lowest if it's full. Returns its position, or `None` if it didn't make it.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.entries.iter().position(|e| entry.score > e.score).unwrap_or(self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Converts days since 1970-01-01 to a Gregorian (year, month, day), after
+/// Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn seed_to_string<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&seed.to_string())
+}
+
+fn seed_from_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(name: &str, score: i32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            ..HighScore::default()
+        }
+    }
+
+    /// A path in the temp directory no other test uses, cleared beforehand.
+    fn scratch_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("high-mtn-patrol-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn write_file(name: &str, source: &str) -> PathBuf {
+        let path = scratch_file(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn scores(table: &HighScores) -> Vec<i32> {
+        table.entries().iter().map(|e| e.score).collect()
+    }
+
+    #[test]
+    fn entries_are_kept_highest_first_below_equal_scores() {
+        let mut table = HighScores::default();
+        assert_eq!(table.insert(entry("a", 50)), Some(0));
+        assert_eq!(table.insert(entry("b", 80)), Some(0));
+        assert_eq!(table.insert(entry("c", 50)), Some(2));
+        assert_eq!(table.insert(entry("d", 10)), Some(3));
+        assert_eq!(scores(&table), [80, 50, 50, 10]);
+        let names: Vec<&str> = table.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["b", "a", "c", "d"]);
+    }
+
+    #[test]
+    fn a_full_table_drops_its_lowest_score() {
+        let mut table = HighScores::default();
+        for score in 1..=MAX_ENTRIES as i32 {
+            assert!(table.qualifies(score * 10));
+            table.insert(entry("x", score * 10));
+        }
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+
+        // Ties with the lowest score and anything below it don't make it
+        assert!(!table.qualifies(10));
+        assert_eq!(table.insert(entry("low", 10)), None);
+        assert!(table.qualifies(15));
+        assert_eq!(table.insert(entry("new", 15)), Some(MAX_ENTRIES - 1));
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+        assert_eq!(*scores(&table).last().unwrap(), 15);
+        assert_eq!(table.insert(entry("top", 1000)), Some(0));
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+    }
+
+    #[test]
+    fn runs_without_points_never_qualify() {
+        let table = HighScores::default();
+        assert!(!table.qualifies(0));
+        assert!(!table.qualifies(-5));
+        assert!(table.qualifies(1));
+    }
+
+    #[test]
+    fn days_convert_to_calendar_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(10_957), (2000, 1, 1));
+        // Leap days, including the one in a year divisible by 400
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(19_783), (2024, 3, 1));
+        // 2100 isn't a leap year
+        assert_eq!(civil_from_days(47_540), (2100, 2, 28));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+        assert_eq!(civil_from_days(20_088), (2024, 12, 31));
+    }
+
+    #[test]
+    fn a_saved_table_loads_back_the_same() {
+        let mut table = HighScores::default();
+        table.insert(HighScore {
+            name: "Ünïcode \"quoted\"".to_string(),
+            score: 1234,
+            date: "2024-02-29".to_string(),
+            seed: u64::MAX,
+            distance: 567,
+            altitude: 89,
+        });
+        table.insert(entry("second", 12));
+
+        let path = scratch_file("round-trip.toml");
+        table.save(&path).unwrap();
+        let loaded = HighScores::load(&path).unwrap();
+        assert_eq!(loaded.entries(), table.entries());
+    }
+
+    #[test]
+    fn a_missing_file_is_an_empty_table() {
+        let table = HighScores::load(&scratch_file("missing.toml")).unwrap();
+        assert!(table.entries().is_empty());
+    }
+
+    #[test]
+    fn files_from_a_newer_version_are_refused() {
+        let path = write_file("newer.toml", &format!("version = {}\n", VERSION + 1));
+        assert!(matches!(HighScores::load(&path), Err(HighScoreError::UnsupportedVersion(v)) if v == VERSION + 1));
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let path = write_file("sparse.toml", "[[entries]]\nscore = 5\n\n[[entries]]\nname = \"Bo\"\nscore = 9\nunknown = true\n");
+        let table = HighScores::load(&path).unwrap();
+        assert_eq!(scores(&table), [9, 5]);
+        assert_eq!(table.entries()[0].name, "Bo");
+        assert_eq!(table.entries()[1], entry("Anonymous", 5));
+    }
+
+    #[test]
+    fn oversized_tables_are_trimmed_on_load() {
+        let source: String = (0..MAX_ENTRIES + 5)
+            .map(|score| format!("[[entries]]\nscore = {}\n", score))
+            .collect();
+        let table = HighScores::load(&write_file("oversized.toml", &source)).unwrap();
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+        assert_eq!(table.entries()[0].score, (MAX_ENTRIES + 4) as i32);
+    }
+}
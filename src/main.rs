@@ -1,271 +1,664 @@
-use ggez::{Context, GameResult};
+use ggez::{Context, GameError, GameResult};
 use ggez::graphics::{self, Color, DrawParam};
 use ggez::event::EventHandler;
 use ggez::input::keyboard::{KeyCode, KeyInput};
-use ggez::conf::{WindowMode, WindowSetup};
+use ggez::input::mouse::MouseButton;
+use ggez::conf::{FullscreenType, WindowMode, WindowSetup};
 use ggez::glam::Vec2 as GgezVec2;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 mod terrain;
 mod player;
 mod background;
 mod boulder;
 mod pickup;
-use terrain::Terrain;
-use player::Player;
-use background::Background;
-use boulder::Boulder;
-use pickup::{Pickup, PickupType};
+mod world;
+mod rng;
+mod replay;
+mod scene;
+mod menu;
+mod tuning;
+mod toast;
+mod viewport;
+mod collision;
+mod camera;
+mod highscores;
+mod powerup;
+use world::{World, FrameInput};
+use replay::Replay;
+use scene::Scene;
+use menu::Menu;
+use tuning::{Tuning, TuningError, TuningWatcher};
+use toast::Toast;
+use viewport::Viewport;
+use terrain::{BrushMode, TerrainStyle};
+use highscores::{HighScore, HighScores};
 
+// Size of the logical playfield everything is simulated and drawn in; it is
+// scaled to fit the window
+const PLAYFIELD_WIDTH: f32 = 640.0;
+const PLAYFIELD_HEIGHT: f32 = 640.0;
 // Constants from the Processing version
-const WINDOW_WIDTH: f32 = 640.0;
-const WINDOW_HEIGHT: f32 = 640.0;
 const ORIGINAL_WIDTH: f32 = 480.0;
 const ORIGINAL_HEIGHT: f32 = 480.0;
-const SCALE_X: f32 = WINDOW_WIDTH / ORIGINAL_WIDTH;
-const SCALE_Y: f32 = WINDOW_HEIGHT / ORIGINAL_HEIGHT;
+const SCALE_X: f32 = PLAYFIELD_WIDTH / ORIGINAL_WIDTH;
+const SCALE_Y: f32 = PLAYFIELD_HEIGHT / ORIGINAL_HEIGHT;
+
+// The simulation always advances in fixed ticks; per-tick speeds are tuned for this rate
+const TICKS_PER_SECOND: u32 = 60;
+const TICK_DT: f32 = 1.0 / TICKS_PER_SECOND as f32;
+
+// Every run is recorded here, in the user data directory
+const RECORDING_FILE: &str = "last_run.hmtr";
+
+const RESOURCE_DIR: &str = "resources";
+const TUNING_FILE: &str = "tuning.toml";
+// Kept in the user data directory, next to the last recording
+const HIGH_SCORE_FILE: &str = "highscores.toml";
+
+/// Command line options.
+struct Options {
+    // Fixed seed for every run, so a run can be reproduced exactly
+    seed: Option<u64>,
+    // Replay file to play back instead of reading live input
+    replay: Option<PathBuf>,
+    // Start with pixel-perfect rendering at the original resolution
+    pixel_perfect: bool,
+    // Terrain style for new runs; F3 cycles through them between runs
+    terrain_style: TerrainStyle,
+}
+
+impl Options {
+    fn from_args() -> GameResult<Options> {
+        let mut options = Options {
+            seed: None,
+            replay: None,
+            pixel_perfect: false,
+            terrain_style: TerrainStyle::default(),
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or_else(|| {
+                        GameError::ConfigError("--seed needs a value".to_string())
+                    })?;
+                    let seed = value.parse().map_err(|_| {
+                        GameError::ConfigError(format!("invalid seed: {}", value))
+                    })?;
+                    options.seed = Some(seed);
+                },
+                "--replay" => {
+                    let path = args.next().ok_or_else(|| {
+                        GameError::ConfigError("--replay needs a file".to_string())
+                    })?;
+                    options.replay = Some(path.into());
+                },
+                "--pixel-perfect" => options.pixel_perfect = true,
+                "--terrain" => {
+                    let value = args.next().ok_or_else(|| {
+                        GameError::ConfigError("--terrain needs a style".to_string())
+                    })?;
+                    options.terrain_style = TerrainStyle::from_name(&value).ok_or_else(|| {
+                        let names: Vec<_> = TerrainStyle::ALL.iter().map(|s| s.name()).collect();
+                        GameError::ConfigError(format!(
+                            "unknown terrain style: {} (expected one of {})",
+                            value,
+                            names.join(", ")
+                        ))
+                    })?;
+                },
+                _ => return Err(GameError::ConfigError(format!("unknown argument: {}", arg))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Restart,
+    QuitToTitle,
+}
 
 struct GameState {
-    state: i32,  // 0 = title, 1 = playing, 2 = game over
-    score: i32,
-    speed_x: f32,
-    speed_x_delta: f32,
-    speed_y: f32,
-    gravity: f32,
-    ascent_speed: f32,
-    collision_counter_on: bool,
+    scene: Scene,
+    options: Options,
+    tuning: Tuning,
+    tuning_watcher: TuningWatcher,
+    toast: Option<Toast>,
+    viewport: Viewport,
+    fullscreen: bool,
+    // Offscreen image at the original resolution, used in pixel-perfect mode
+    low_res_target: Option<graphics::Image>,
     title_text: graphics::Image,
-    terrain: Terrain,
-    background: Background,
+    world: World,
     mouse_pos: GgezVec2,
-    player: Player,
-    boulders: Vec<Boulder>,
-    pickups: Vec<Pickup>,
+    // What hovering the mouse does to the ground; the scroll wheel cycles it
+    brush_mode: BrushMode,
+    // Buttons pressed during play and still held, in the order they went down.
+    // The last one picks the brush over `brush_mode`
+    brush_buttons: Vec<MouseButton>,
+    // One-shot key presses collected between updates
+    pending_input: FrameInput,
+    // What the last simulation step was given, live or from the replay
+    last_input: FrameInput,
+    recording: Replay,
+    playback: Option<Replay>,
+    playback_tick: usize,
+    pause_menu: Menu<PauseAction>,
+    // `None` if the file on disk couldn't be read, so it's never overwritten
+    high_scores: Option<HighScores>,
+    // The name being typed in after a run that made the table
+    name_entry: Option<String>,
+    // Row of the table to highlight after entering a name
+    new_high_score: Option<usize>,
 }
 
 impl GameState {
-    fn new(ctx: &Context) -> GameResult<GameState> {
-        let mut state = GameState {
-            state: 0,
-            score: 0,
-            speed_x: 4.0 * SCALE_X,
-            speed_x_delta: 4.0 * SCALE_X,
-            speed_y: 1.0 * SCALE_Y,
-            gravity: 15.0 * SCALE_Y,
-            ascent_speed: 0.0,
-            collision_counter_on: false,
+    fn new(ctx: &Context, options: Options) -> GameResult<GameState> {
+        let playback = match &options.replay {
+            Some(path) => Some(Replay::read_from(&mut std::fs::File::open(path)?)?),
+            None => None,
+        };
+
+        let tuning = load_tuning(ctx)?;
+        let (window_width, window_height) = ctx.gfx.drawable_size();
+        let viewport = Viewport::new(window_width, window_height, options.pixel_perfect);
+        let terrain_style = options.terrain_style;
+
+        let (high_scores, toast) = match HighScores::load(&high_score_path(ctx)) {
+            Ok(table) => (Some(table), None),
+            Err(e) => {
+                eprintln!("High scores disabled: {}", e);
+                (None, Some(Toast::new(vec!["High scores disabled".to_string(), e.to_string()], Color::RED)))
+            },
+        };
+
+        let state = GameState {
+            scene: Scene::Title,
+            options,
             title_text: graphics::Image::from_path(ctx, "/TitleText.png")?,
-            terrain: Terrain::new(
-                WINDOW_WIDTH,
-                WINDOW_HEIGHT - WINDOW_HEIGHT/6.0,
-                4.0 * SCALE_X
+            world: World::new(0, terrain_style, &tuning),
+            recording: Replay::new(0, terrain_style, &tuning),
+            tuning,
+            tuning_watcher: TuningWatcher::new(tuning_file_path(ctx)),
+            toast,
+            viewport,
+            fullscreen: false,
+            low_res_target: None,
+            mouse_pos: GgezVec2::new(0.0, PLAYFIELD_HEIGHT/6.0),
+            brush_mode: BrushMode::default(),
+            brush_buttons: Vec::new(),
+            pending_input: FrameInput::default(),
+            last_input: FrameInput::default(),
+            playback,
+            playback_tick: 0,
+            pause_menu: Menu::new(
+                vec![
+                    ("Resume", PauseAction::Resume),
+                    ("Restart", PauseAction::Restart),
+                    ("Quit to Title", PauseAction::QuitToTitle),
+                ],
+                PLAYFIELD_WIDTH/2.0,
+                PLAYFIELD_HEIGHT/2.0
             ),
-            background: Background::new(WINDOW_WIDTH, WINDOW_HEIGHT),
-            mouse_pos: GgezVec2::new(0.0, WINDOW_HEIGHT/6.0),
-            player: Player::new(WINDOW_WIDTH/4.0, WINDOW_HEIGHT/2.0),
-            boulders: Vec::new(),
-            pickups: Vec::new(),
+            high_scores,
+            name_entry: None,
+            new_high_score: None,
         };
-        
-        state.reset_game_environment(ctx)?;
         Ok(state)
     }
 
-    fn reset_game_environment(&mut self, _ctx: &Context) -> GameResult {
-        // Reset boulders
-        self.boulders.clear();
-        for _ in 0..2 {
-            self.boulders.push(Boulder::new(
-                WINDOW_WIDTH,
-                rand::random::<f32>() * WINDOW_HEIGHT * 0.75,
-                WINDOW_WIDTH
-            ));
-        }
-
-        // Reset pickups
-        self.pickups.clear();
-        // Add coins
-        for i in 0..2 {
-            self.pickups.push(Pickup::new(
-                WINDOW_WIDTH + i as f32 * 200.0,
-                rand::random::<f32>() * WINDOW_HEIGHT,
-                PickupType::Coin
-            ));
-        }
-        // Add gems
-        for i in 0..2 {
-            self.pickups.push(Pickup::new(
-                WINDOW_WIDTH + i as f32 * 300.0,
-                rand::random::<f32>() * WINDOW_HEIGHT,
-                PickupType::Gem
-            ));
-        }
+    /// Applies a tuning file that changed on disk, or reports why it couldn't be used.
+    fn reload_tuning(&mut self, result: Result<Tuning, TuningError>) {
+        let (lines, color) = match result {
+            Ok(tuning) => {
+                let mut lines = vec!["Tuning reloaded".to_string()];
+                let changes = tuning.changes_from(&self.tuning);
+                if changes.is_empty() {
+                    lines.push("no values changed".to_string());
+                }
+                lines.extend(changes);
 
-        // Reset player position
-        self.player = Player::new(WINDOW_WIDTH/4.0, WINDOW_HEIGHT/2.0);
-
-        // Reset terrain
-        self.terrain = Terrain::new(
-            WINDOW_WIDTH,
-            WINDOW_HEIGHT - WINDOW_HEIGHT/6.0,
-            4.0 * SCALE_X
-        );
-
-        // Reset game state variables
-        self.score = 0;
-        self.speed_x = 4.0 * SCALE_X;
-        self.speed_x_delta = 4.0 * SCALE_X;
-        self.speed_y = 1.0 * SCALE_Y;
-        self.gravity = 15.0 * SCALE_Y;
-        self.ascent_speed = 0.0;
-        self.collision_counter_on = false;
+                // A replay plays back with the tuning it was recorded with
+                if self.playback.is_some() {
+                    lines.push("replay keeps its own tuning".to_string());
+                } else {
+                    self.world.apply_tuning(&tuning);
+                    self.recording.record_tuning(&tuning);
+                }
+                self.tuning = tuning;
+                (lines, Color::GREEN)
+            },
+            Err(e) => (vec!["Tuning file not applied".to_string(), e.to_string()], Color::RED),
+        };
+        self.toast = Some(Toast::new(lines, color));
+    }
 
+    fn reset_game_environment(&mut self, _ctx: &Context) -> GameResult {
+        let (seed, terrain_style, tuning) = match &self.playback {
+            Some(replay) => (replay.seed, replay.terrain_style, &replay.tuning),
+            None => (self.options.seed.unwrap_or_else(rand::random), self.options.terrain_style, &self.tuning),
+        };
+        self.world = World::new(seed, terrain_style, tuning);
+        self.pending_input = FrameInput::default();
+        self.last_input = FrameInput::default();
+        self.recording = Replay::new(seed, terrain_style, tuning);
+        self.playback_tick = 0;
         Ok(())
     }
-}
 
-
-impl EventHandler for GameState {
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        match self.state {
-            0 => {}, // Title screen
-            1 => {   // Playing
-                // Update terrain
-                self.terrain.update(
-                    self.mouse_pos,
-                    self.speed_x,
-                    self.ascent_speed,
-                    &mut self.player
-                );
-
-                // Update player with gravity and terrain physics
-                self.player.update(ctx.time.delta().as_secs_f32(), self.gravity, &mut self.speed_x);
-
-                // Check for keyboard input
-                let keyboard = &ctx.keyboard;
-                
-                if keyboard.is_key_pressed(KeyCode::Space) {
-                    self.player.jump();
-                }
-                
-                if keyboard.is_key_pressed(KeyCode::Left) {
-                    self.player.move_left();
-                } else if keyboard.is_key_pressed(KeyCode::Right) {
-                    self.player.move_right();
-                } else {
-                    self.player.stop_horizontal();
+    /// Returns the input for the next simulation tick, either from the replay
+    /// being played back or from the live mouse and keyboard. `None` means the
+    /// replay has run out.
+    fn next_input(&mut self, ctx: &Context) -> Option<FrameInput> {
+        match &self.playback {
+            Some(replay) => {
+                if let Some(tuning) = replay.tuning_change_at(self.playback_tick) {
+                    self.world.apply_tuning(tuning);
                 }
+                let input = replay.inputs.get(self.playback_tick).copied();
+                self.playback_tick += 1;
+                input
+            },
+            None => {
+                let input = self.take_frame_input(ctx);
+                self.recording.record(input);
+                Some(input)
+            },
+        }
+    }
 
-                // Check collisions
-                if self.player.pos.y > WINDOW_HEIGHT {
-                    self.collision_counter_on = true;
-                    self.state = 2;  // Game over
-                } else if let Some(terrain_height) = self.terrain.get_height_at(self.player.pos.x) {
-                    // Add a small buffer to prevent premature ground detection
-                    if self.player.pos.y + 30.0 > terrain_height - 5.0 {  
-                        self.player.pos.y = terrain_height - 30.0;  // Align bottom of wheels with terrain
-                        self.player.velocity.y = 0.0;
-                        self.player.on_ground = true;
-                    } else {
-                        self.player.on_ground = false;
-                    }
-                }
+    /// Moves to `next`, running the exit hook of the current scene and the
+    /// enter hook of the new one. Transitions the current scene doesn't allow
+    /// are ignored.
+    fn set_scene(&mut self, ctx: &Context, next: Scene) -> GameResult {
+        if !self.scene.can_transition_to(next) {
+            return Ok(());
+        }
+        let previous = self.scene;
+        // A button held through a scene change, like the click that started
+        // the run, doesn't sculpt
+        self.brush_buttons.clear();
+        self.exit_scene(ctx, next);
+        self.scene = next;
+        self.enter_scene(ctx, previous)
+    }
+
+    fn enter_scene(&mut self, ctx: &Context, from: Scene) -> GameResult {
+        match self.scene {
+            // Resuming from the pause menu continues the current run
+            Scene::Playing if from != Scene::Paused => self.reset_game_environment(ctx)?,
+            Scene::Paused => self.pause_menu.reset(),
+            // Replays aren't new achievements
+            Scene::GameOver if self.playback.is_none()
+                && self.high_scores.as_ref().is_some_and(|table| table.qualifies(self.world.score)) => {
+                self.name_entry = Some(String::new());
             },
-            2 => {}, // Game over
             _ => {},
         }
         Ok(())
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(
-            ctx, 
-            Color::from_rgb(0, 0, 40) // #000028
-        );
+    fn exit_scene(&mut self, ctx: &Context, to: Scene) {
+        match self.scene {
+            // A run ends when leaving play for anything but the pause menu
+            Scene::Playing if to != Scene::Paused => self.save_recording(ctx),
+            Scene::Paused if to == Scene::Title => self.save_recording(ctx),
+            Scene::GameOver => self.name_entry = None,
+            Scene::HighScores => self.new_high_score = None,
+            _ => {},
+        }
+    }
+
+    fn apply_pause_action(&mut self, ctx: &Context, action: PauseAction) -> GameResult {
+        match action {
+            PauseAction::Resume => self.set_scene(ctx, Scene::Playing),
+            PauseAction::Restart => {
+                self.save_recording(ctx);
+                self.reset_game_environment(ctx)?;
+                self.set_scene(ctx, Scene::Playing)
+            },
+            PauseAction::QuitToTitle => self.set_scene(ctx, Scene::Title),
+        }
+    }
 
-        match self.state {
-            0 => { // Title screen
+    /// Puts the finished run in the high score table under the name typed in,
+    /// saves the table and shows it.
+    fn submit_name(&mut self, ctx: &Context) -> GameResult {
+        let (Some(name), Some(table)) = (self.name_entry.take(), &mut self.high_scores) else {
+            return Ok(());
+        };
+        let scoring = &self.world.tuning.scoring;
+        let name = name.trim();
+        let entry = HighScore {
+            name: if name.is_empty() { HighScore::default().name } else { name.to_string() },
+            score: self.world.score,
+            date: highscores::today(),
+            seed: self.world.rng.seed(),
+            distance: scoring.metres(self.world.stats.distance),
+            altitude: scoring.metres(self.world.stats.best_altitude),
+        };
+        self.new_high_score = table.insert(entry);
+        if let Err(e) = table.save(&high_score_path(ctx)) {
+            eprintln!("Could not save high scores: {}", e);
+            self.toast = Some(Toast::new(vec!["High scores not saved".to_string(), e.to_string()], Color::RED));
+        }
+        self.set_scene(ctx, Scene::HighScores)
+    }
+
+    fn toggle_fullscreen(&mut self, ctx: &mut Context) -> GameResult {
+        self.fullscreen = !self.fullscreen;
+        ctx.gfx.set_fullscreen(if self.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        })
+    }
+
+    /// Draws the current scene in playfield coordinates.
+    fn draw_scene(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, screen: graphics::Rect) -> GameResult {
+        // Fraction of a tick that has elapsed since the last simulation step
+        let alpha = (ctx.time.remaining_update_time().as_secs_f32() / TICK_DT).min(1.0);
+
+        match self.scene {
+            Scene::Title => {
                 let params = graphics::DrawParam::default()
-                    .dest(GgezVec2::new(0.0, WINDOW_HEIGHT/4.0))
+                    .dest(GgezVec2::new(0.0, PLAYFIELD_HEIGHT/4.0))
                     .scale(GgezVec2::new(
-                        WINDOW_WIDTH/self.title_text.width() as f32,
+                        PLAYFIELD_WIDTH/self.title_text.width() as f32,
                         SCALE_Y
                     ));
                 canvas.draw(&self.title_text, params);
-            },
-            1 => { // Playing
-                self.background.update(self.speed_x, self.ascent_speed);
-                self.background.draw(ctx, &mut canvas)?;
-                self.terrain.draw(ctx, &mut canvas, WINDOW_HEIGHT)?;
-
-                // Draw score
-                let score_text = format!("Score: {}", self.score);
-                let score_display = graphics::Text::new(score_text);
-                canvas.draw(&score_display, DrawParam::default()
-                    .dest(GgezVec2::new(20.0, 20.0))
-                    .color(Color::RED));
 
-                // Update and draw boulders
-                for boulder in &mut self.boulders {
-                    boulder.update(self.speed_x, self.ascent_speed);
-                    
-                    // Check if boulder is off screen
-                    if boulder.pos.x + boulder.width < 0.0 {
-                        boulder.reposition(WINDOW_WIDTH, WINDOW_HEIGHT);
-                    }
-                    
-                    // Check collision with player
-                    if boulder.collides_with_player(self.player.pos, self.player.width, self.player.height) {
-                        self.collision_counter_on = true;
-                        self.state = 2; // Game over
-                    }
-                    
-                    boulder.draw(ctx, &mut canvas)?;
-                }
+                let hint = graphics::Text::new("Return: start    H: high scores");
+                canvas.draw(&hint, DrawParam::default()
+                    .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 130.0, PLAYFIELD_HEIGHT * 3.0/4.0))
+                    .color(Color::WHITE));
+            },
+            Scene::Playing => {
+                self.world.draw(ctx, canvas, screen, alpha)?;
+                self.draw_hud(canvas);
+            },
+            Scene::Paused => {
+                // The run is frozen, so show the last simulated frame dimmed
+                self.world.draw(ctx, canvas, screen, 1.0)?;
+                self.draw_hud(canvas);
 
-                // Update and draw pickups
-                for pickup in &mut self.pickups {
-                    pickup.update(self.speed_x, self.ascent_speed);
-                    
-                    // Check if pickup is off screen
-                    if pickup.pos.x + pickup.width < 0.0 {
-                        pickup.reposition(WINDOW_WIDTH, WINDOW_HEIGHT);
-                    }
-                    
-                    // Check collision with player
-                    if pickup.collides_with_player(self.player.pos, self.player.width, self.player.height) {
-                        pickup.collected = true;
-                        self.score += pickup.value;
-                        self.speed_x += 5.0 * SCALE_X;
-                    }
-                    
-                    pickup.draw(ctx, &mut canvas)?;
-                }
+                let dim = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(0.0, 0.0, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT),
+                    Color::new(0.0, 0.0, 0.0, 0.6),
+                )?;
+                canvas.draw(&dim, DrawParam::default());
 
-                // Only draw player if not collided
-                if !self.collision_counter_on {
-                    self.player.draw(ctx, &mut canvas)?;
-                }
+                let paused = graphics::Text::new("Paused");
+                canvas.draw(&paused, DrawParam::default()
+                    .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 80.0, PLAYFIELD_HEIGHT/3.0))
+                    .color(Color::RED));
+                self.pause_menu.draw(canvas);
             },
-            2 => { // Game over
-                self.background.draw(ctx, &mut canvas)?;
-                self.terrain.draw(ctx, &mut canvas, WINDOW_HEIGHT)?;
+            Scene::GameOver => {
+                self.world.draw_scenery(ctx, canvas, screen, 1.0)?;
                 
                 // Draw game over text
                 let game_over = graphics::Text::new("Game Over");
-                let score_text = graphics::Text::new(format!("Final Score: {}", self.score));
+                let score_text = graphics::Text::new(format!("Final Score: {}", self.world.score));
+                let seed_text = graphics::Text::new(format!("Seed: {}", self.world.rng.seed()));
+                let scoring = &self.world.tuning.scoring;
+                let progress_text = graphics::Text::new(format!(
+                    "Best altitude: {} m\nDistance: {} m",
+                    scoring.metres(self.world.stats.best_altitude),
+                    scoring.metres(self.world.stats.distance)
+                ));
                 
                 canvas.draw(&game_over, DrawParam::default()
-                    .dest(GgezVec2::new(WINDOW_WIDTH/2.0 - 100.0, WINDOW_HEIGHT/3.0))
+                    .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 100.0, PLAYFIELD_HEIGHT/3.0))
                     .color(Color::RED));
                     
                 canvas.draw(&score_text, DrawParam::default()
-                    .dest(GgezVec2::new(WINDOW_WIDTH/2.0 - 80.0, WINDOW_HEIGHT/2.0))
+                    .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 80.0, PLAYFIELD_HEIGHT/2.0))
+                    .color(Color::WHITE));
+
+                canvas.draw(&progress_text, DrawParam::default()
+                    .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 80.0, PLAYFIELD_HEIGHT/2.0 + 30.0))
                     .color(Color::WHITE));
+
+                canvas.draw(&seed_text, DrawParam::default()
+                    .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 80.0, PLAYFIELD_HEIGHT/2.0 + 80.0))
+                    .color(Color::WHITE));
+
+                if let Some(name) = &self.name_entry {
+                    let prompt = graphics::Text::new(format!(
+                        "New high score! Enter your name:\n{}_",
+                        name
+                    ));
+                    canvas.draw(&prompt, DrawParam::default()
+                        .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 130.0, PLAYFIELD_HEIGHT/2.0 + 130.0))
+                        .color(Color::from_rgb(218, 204, 62)));
+                }
             },
-            _ => {},
+            Scene::HighScores => self.draw_high_scores(canvas),
+        }
+
+        if let Some(toast) = &self.toast {
+            toast.draw(canvas, GgezVec2::new(20.0, PLAYFIELD_HEIGHT - 20.0));
         }
 
-        canvas.finish(ctx)?;
+        Ok(())
+    }
+
+    fn draw_hud(&self, canvas: &mut graphics::Canvas) {
+        let score_text = format!("Score: {}", self.world.score);
+        let score_display = graphics::Text::new(score_text);
+        canvas.draw(&score_display, DrawParam::default()
+            .dest(GgezVec2::new(20.0, 20.0))
+            .color(Color::RED));
+
+        let scoring = &self.world.tuning.scoring;
+        let stats = &self.world.stats;
+        let progress = graphics::Text::new(format!(
+            "Altitude: {} m\nDistance: {} m",
+            scoring.metres(stats.altitude),
+            scoring.metres(stats.distance)
+        ));
+        canvas.draw(&progress, DrawParam::default()
+            .dest(GgezVec2::new(PLAYFIELD_WIDTH - 160.0, 20.0))
+            .color(Color::WHITE));
+
+        // Fuel gauge, turning red when the tank is nearly empty
+        let fuel = self.world.player.fuel / self.world.tuning.fuel.capacity;
+        let fuel_color = if fuel < 0.25 { Color::RED } else { Color::from_rgb(92, 226, 0) };
+        draw_gauge(canvas, "Fuel", 20.0, fuel, fuel_color);
+        draw_gauge(canvas, "Truck", 40.0, self.world.player.health_fraction(), Color::from_rgb(127, 183, 190));
+        // Sculpting ink, greyed out while there's too little to move the ground
+        let ink = self.world.ink / self.world.tuning.sculpting.capacity;
+        let ink_color = if self.world.ink < 1.0 { Color::from_rgb(90, 90, 90) } else { Color::from_rgb(70, 100, 230) };
+        draw_gauge(canvas, "Ink", 60.0, ink, ink_color);
+        // The brush actually in use, whether from the wheel, a held button or the replay
+        canvas.draw(&graphics::Text::new(self.last_input.brush.name()), DrawParam::default()
+            .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 + 70.0, 60.0))
+            .color(ink_color));
+
+        // One timer per running power-up, with a bar that empties as it runs out
+        for (i, effect) in self.world.player.effects.active().iter().enumerate() {
+            let top = 50.0 + i as f32 * 24.0;
+            let color = effect.power_up.color();
+            let label = graphics::Text::new(format!("{} {:.1}s", effect.power_up.name(), effect.remaining));
+            canvas.draw(&label, DrawParam::default()
+                .dest(GgezVec2::new(20.0, top))
+                .color(color));
+
+            let fraction = (effect.remaining / effect.duration).clamp(0.0, 1.0);
+            canvas.draw(&graphics::Quad, DrawParam::default()
+                .dest(GgezVec2::new(20.0, top + 17.0))
+                .scale(GgezVec2::new(120.0 * fraction, 3.0))
+                .color(color));
+        }
+    }
+
+    fn draw_high_scores(&self, canvas: &mut graphics::Canvas) {
+        let heading = graphics::Text::new("High Scores");
+        canvas.draw(&heading, DrawParam::default()
+            .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 50.0, 60.0))
+            .color(Color::RED));
+
+        let Some(table) = &self.high_scores else {
+            let text = graphics::Text::new("High scores are unavailable");
+            canvas.draw(&text, DrawParam::default()
+                .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 110.0, PLAYFIELD_HEIGHT/3.0))
+                .color(Color::WHITE));
+            return;
+        };
+
+        // Left edge of each column
+        let columns = [20.0, 50.0, 170.0, 240.0, 310.0, 380.0, 480.0];
+        let header = ["#", "Name", "Score", "Alt", "Dist", "Date", "Seed"];
+        let rows = table.entries().iter().enumerate().map(|(i, entry)| {
+            [
+                (i + 1).to_string(),
+                entry.name.clone(),
+                entry.score.to_string(),
+                format!("{} m", entry.altitude),
+                format!("{} m", entry.distance),
+                entry.date.clone(),
+                entry.seed.to_string(),
+            ]
+        });
+        let header = header.map(str::to_string);
+        for (row, cells) in std::iter::once(header).chain(rows).enumerate() {
+            let color = match row {
+                0 => Color::from_rgb(127, 183, 190), // #7FB7BE
+                _ if self.new_high_score == Some(row - 1) => Color::from_rgb(218, 204, 62), // #DACC3E
+                _ => Color::WHITE,
+            };
+            for (x, cell) in columns.iter().zip(cells) {
+                let mut text = graphics::Text::new(cell);
+                text.set_scale(12.0);
+                canvas.draw(&text, DrawParam::default()
+                    .dest(GgezVec2::new(*x, 120.0 + row as f32 * 30.0))
+                    .color(color));
+            }
+        }
+
+        if table.entries().is_empty() {
+            let text = graphics::Text::new("No runs yet");
+            canvas.draw(&text, DrawParam::default()
+                .dest(GgezVec2::new(PLAYFIELD_WIDTH/2.0 - 50.0, 180.0))
+                .color(Color::WHITE));
+        }
+    }
+
+    fn save_recording(&self, ctx: &Context) {
+        if self.playback.is_some() {
+            return;
+        }
+        let dir = ctx.fs.user_data_dir();
+        let path = dir.join(RECORDING_FILE);
+        let result = std::fs::create_dir_all(dir)
+            .and_then(|_| self.recording.write_to(&mut std::fs::File::create(&path)?));
+        match result {
+            Ok(()) => eprintln!("Replay saved to {}", path.display()),
+            Err(e) => eprintln!("Could not save replay: {}", e),
+        }
+    }
+
+    /// Combines the mouse, held keys and queued key presses into the input
+    /// for one simulation step. Hovering sculpts with the brush picked by the
+    /// scroll wheel; holding a mouse button swaps in its own brush for as
+    /// long as it's held.
+    fn take_frame_input(&mut self, ctx: &Context) -> FrameInput {
+        let keyboard = &ctx.keyboard;
+        let pending = std::mem::take(&mut self.pending_input);
+        let brush = self.brush_buttons.last()
+            .and_then(|&button| button_brush(button))
+            .unwrap_or(self.brush_mode);
+        FrameInput {
+            mouse_pos: self.mouse_pos,
+            jump: keyboard.is_key_pressed(KeyCode::Space),
+            throttle: keyboard.is_key_pressed(KeyCode::Right),
+            brake: keyboard.is_key_pressed(KeyCode::Left),
+            brush,
+            ..pending
+        }
+    }
+}
+
+
+impl EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if let Some(result) = self.tuning_watcher.poll() {
+            self.reload_tuning(result);
+        }
+
+        // Drain every tick that is due, even outside of play, so that entering
+        // a run doesn't fast-forward through time spent on other screens
+        while ctx.time.check_update_time(TICKS_PER_SECOND) {
+            if let Some(toast) = &mut self.toast {
+                toast.update(TICK_DT);
+                if toast.is_finished() {
+                    self.toast = None;
+                }
+            }
+
+            if self.scene == Scene::Playing {
+                match self.next_input(ctx) {
+                    Some(input) => {
+                        self.world.step(&input, TICK_DT);
+                        self.last_input = input;
+                    },
+                    None => self.world.game_over = true,  // Replay finished
+                }
+                if self.world.game_over {
+                    self.set_scene(ctx, Scene::GameOver)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let sky = Color::from_rgb(0, 0, 40); // #000028
+
+        if self.viewport.pixel_perfect() {
+            // Draw at the original resolution, then blow that up with crisp pixels
+            let target = match &self.low_res_target {
+                Some(target) => target.clone(),
+                None => {
+                    let target = graphics::Image::new_canvas_image(
+                        ctx,
+                        ctx.gfx.surface_format(),
+                        ORIGINAL_WIDTH as u32,
+                        ORIGINAL_HEIGHT as u32,
+                        1
+                    );
+                    self.low_res_target = Some(target.clone());
+                    target
+                },
+            };
+
+            let mut canvas = graphics::Canvas::from_image(ctx, target.clone(), sky);
+            let screen = graphics::Rect::new(0.0, 0.0, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT);
+            canvas.set_screen_coordinates(screen);
+            self.draw_scene(ctx, &mut canvas, screen)?;
+            canvas.finish(ctx)?;
+
+            let mut frame = graphics::Canvas::from_frame(ctx, Color::BLACK);
+            frame.set_sampler(graphics::Sampler::nearest_clamp());
+            self.viewport.draw_pixel_perfect(&mut frame, &target);
+            frame.finish(ctx)?;
+        } else {
+            let mut canvas = graphics::Canvas::from_frame(ctx, sky);
+            let screen = self.viewport.screen_coordinates();
+            canvas.set_screen_coordinates(screen);
+            self.draw_scene(ctx, &mut canvas, screen)?;
+            self.viewport.draw_letterbox(ctx, &mut canvas)?;
+            canvas.finish(ctx)?;
+        }
         Ok(())
     }
 
@@ -277,7 +670,10 @@ impl EventHandler for GameState {
         _dx: f32,
         _dy: f32,
     ) -> GameResult {
-        self.mouse_pos = GgezVec2::new(x, y);
+        self.mouse_pos = self.viewport.to_playfield(GgezVec2::new(x, y));
+        if self.scene == Scene::Paused {
+            self.pause_menu.select_at(self.mouse_pos);
+        }
         Ok(())
     }
 
@@ -287,62 +683,185 @@ impl EventHandler for GameState {
         input: KeyInput,
         _repeated: bool,
     ) -> GameResult {
+        // Typing a name takes over the keyboard
+        if let Some(name) = &mut self.name_entry {
+            match input.keycode {
+                Some(KeyCode::Return) => self.submit_name(ctx)?,
+                Some(KeyCode::Back) => {
+                    name.pop();
+                },
+                _ => {},
+            }
+            return Ok(());
+        }
+
         match input.keycode {
             Some(KeyCode::Return) => {
-                match self.state {
-                    0 => {
-                        // Start new game
-                        self.state = 1;
-                        self.reset_game_environment(ctx)?;
-                    },
-                    2 => {
-                        // Return to title screen from game over
-                        self.state = 0;
-                    },
-                    _ => {},
+                match self.scene {
+                    Scene::Title => self.set_scene(ctx, Scene::Playing)?,
+                    Scene::GameOver | Scene::HighScores => self.set_scene(ctx, Scene::Title)?,
+                    Scene::Paused => self.apply_pause_action(ctx, self.pause_menu.selected())?,
+                    Scene::Playing => {},
                 }
             },
-            Some(KeyCode::A) => {
-                self.speed_x += 0.2;
+            Some(KeyCode::H) if self.scene == Scene::Title => self.set_scene(ctx, Scene::HighScores)?,
+            Some(KeyCode::F11) => self.toggle_fullscreen(ctx)?,
+            Some(KeyCode::F2) => self.viewport.set_pixel_perfect(!self.viewport.pixel_perfect()),
+            // The style changes how the ground behaves, so it can't change mid-run
+            Some(KeyCode::F3) if matches!(self.scene, Scene::Title | Scene::GameOver) => {
+                let style = self.options.terrain_style.next();
+                self.options.terrain_style = style;
+                self.world.terrain.set_style(style);
+                self.toast = Some(Toast::new(vec![format!("Terrain style: {}", style.name())], Color::WHITE));
             },
-            Some(KeyCode::S) => {
-                self.speed_x -= 0.2;
-                if self.speed_x < 0.0 {
-                    self.speed_x = 0.0;
+            Some(KeyCode::Escape) => {
+                match self.scene {
+                    Scene::Playing => self.set_scene(ctx, Scene::Paused)?,
+                    Scene::Paused => self.set_scene(ctx, Scene::Playing)?,
+                    Scene::HighScores => self.set_scene(ctx, Scene::Title)?,
+                    _ => {},
                 }
             },
-            Some(KeyCode::R) => {
-                self.terrain.realign();
-            },
-            Some(KeyCode::Key0) => self.state = 0,
-            Some(KeyCode::Key1) => self.state = 1,
+            Some(KeyCode::Up) if self.scene == Scene::Paused => self.pause_menu.select_previous(),
+            Some(KeyCode::Down) if self.scene == Scene::Paused => self.pause_menu.select_next(),
+            Some(KeyCode::A) if self.scene == Scene::Playing => self.pending_input.speed_up = true,
+            Some(KeyCode::S) if self.scene == Scene::Playing => self.pending_input.slow_down = true,
+            Some(KeyCode::Key0) => self.set_scene(ctx, Scene::Title)?,
+            Some(KeyCode::Key1) => self.set_scene(ctx, Scene::Playing)?,
             _ => (),
         }
         Ok(())
     }
 
     fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        match self.scene {
+            Scene::Playing if button_brush(button).is_some() && !self.brush_buttons.contains(&button) => {
+                self.brush_buttons.push(button);
+            },
+            Scene::Title => self.set_scene(ctx, Scene::Playing)?,
+            Scene::HighScores => self.set_scene(ctx, Scene::Title)?,
+            Scene::Paused => {
+                let pos = self.viewport.to_playfield(GgezVec2::new(x, y));
+                if let Some(action) = self.pause_menu.select_at(pos) {
+                    self.apply_pause_action(ctx, action)?;
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
         &mut self,
         _ctx: &mut Context,
-        _button: ggez::event::MouseButton,
+        button: MouseButton,
         _x: f32,
         _y: f32,
     ) -> GameResult {
-        if self.state == 0 {
-            self.state = 1;
+        self.brush_buttons.retain(|&held| held != button);
+        Ok(())
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        if self.scene == Scene::Playing && self.playback.is_none() {
+            if y > 0.0 {
+                self.brush_mode = self.brush_mode.next();
+            } else if y < 0.0 {
+                self.brush_mode = self.brush_mode.previous();
+            }
         }
         Ok(())
     }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        if let Some(name) = &mut self.name_entry {
+            if !character.is_control() && name.chars().count() < highscores::MAX_NAME_LENGTH {
+                name.push(character);
+            }
+        }
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.viewport.resize(width, height);
+        Ok(())
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) -> GameResult {
+        if !gained && self.scene == Scene::Playing {
+            self.set_scene(ctx, Scene::Paused)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the tuning file from the resources, falling back to the defaults if there is none.
+fn load_tuning(ctx: &Context) -> GameResult<Tuning> {
+    let path = Path::new("/").join(TUNING_FILE);
+    if !ctx.fs.exists(&path) {
+        return Ok(Tuning::default());
+    }
+    let mut source = String::new();
+    ctx.fs.open(&path)?.read_to_string(&mut source)?;
+    Tuning::from_toml(&source).map_err(|e| GameError::ConfigError(e.to_string()))
+}
+
+/// The brush a mouse button sculpts with while it's held, if it has one.
+fn button_brush(button: MouseButton) -> Option<BrushMode> {
+    match button {
+        MouseButton::Left => Some(BrushMode::Raise),
+        MouseButton::Right => Some(BrushMode::Lower),
+        MouseButton::Middle => Some(BrushMode::Smooth),
+        _ => None,
+    }
+}
+
+/// A labelled bar across the top middle of the HUD, filled to `fraction`.
+fn draw_gauge(canvas: &mut graphics::Canvas, label: &str, top: f32, fraction: f32, color: Color) {
+    let left = PLAYFIELD_WIDTH/2.0 - 60.0;
+    canvas.draw(&graphics::Text::new(label), DrawParam::default()
+        .dest(GgezVec2::new(left - 55.0, top))
+        .color(color));
+    canvas.draw(&graphics::Quad, DrawParam::default()
+        .dest(GgezVec2::new(left, top + 2.0))
+        .scale(GgezVec2::new(120.0, 12.0))
+        .color(Color::new(1.0, 1.0, 1.0, 0.2)));
+    canvas.draw(&graphics::Quad, DrawParam::default()
+        .dest(GgezVec2::new(left, top + 2.0))
+        .scale(GgezVec2::new(120.0 * fraction.clamp(0.0, 1.0), 12.0))
+        .color(color));
+}
+
+fn high_score_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_data_dir().join(HIGH_SCORE_FILE)
+}
+
+/// Where the tuning file lives on disk, for watching it while the game runs.
+fn tuning_file_path(ctx: &Context) -> PathBuf {
+    let local = Path::new(RESOURCE_DIR).join(TUNING_FILE);
+    if local.exists() {
+        local
+    } else {
+        ctx.fs.resources_dir().join(TUNING_FILE)
+    }
 }
 
 fn main() -> GameResult {
+    let options = Options::from_args()?;
     let cb = ggez::ContextBuilder::new("high-mountain-trucker", "you")
-        .add_resource_path("resources")  // Add this line
+        .add_resource_path(RESOURCE_DIR)
         .window_setup(WindowSetup::default().title("High Mountain Trucker"))
         .window_mode(WindowMode::default()
-            .dimensions(WINDOW_WIDTH, WINDOW_HEIGHT));
+            .dimensions(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT)
+            .resizable(true));
     
     let (ctx, event_loop) = cb.build()?;
-    let state = GameState::new(&ctx)?;
+    let state = GameState::new(&ctx, options)?;
     ggez::event::run(ctx, event_loop, state)
 }
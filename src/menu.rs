@@ -0,0 +1,72 @@
+use ggez::graphics::{self, Canvas, Color, DrawParam, Rect};
+use ggez::glam::Vec2;
+
+const ITEM_WIDTH: f32 = 200.0;
+const ITEM_HEIGHT: f32 = 40.0;
+
+/// A vertical list of choices that can be driven by keyboard or mouse.
+pub struct Menu<T: Copy> {
+    items: Vec<(&'static str, T)>,
+    selected: usize,
+    center_x: f32,
+    top: f32,
+}
+
+impl<T: Copy> Menu<T> {
+    pub fn new(items: Vec<(&'static str, T)>, center_x: f32, top: f32) -> Self {
+        Menu {
+            items,
+            selected: 0,
+            center_x,
+            top,
+        }
+    }
+
+    pub fn selected(&self) -> T {
+        self.items[self.selected].1
+    }
+
+    pub fn reset(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    /// Selects the item under `point`, returning it if there is one.
+    pub fn select_at(&mut self, point: Vec2) -> Option<T> {
+        let index = (0..self.items.len()).find(|&i| self.item_rect(i).contains(point))?;
+        self.selected = index;
+        Some(self.items[index].1)
+    }
+
+    fn item_rect(&self, index: usize) -> Rect {
+        Rect::new(
+            self.center_x - ITEM_WIDTH/2.0,
+            self.top + index as f32 * ITEM_HEIGHT,
+            ITEM_WIDTH,
+            ITEM_HEIGHT
+        )
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        for (i, (label, _)) in self.items.iter().enumerate() {
+            let rect = self.item_rect(i);
+            let color = if i == self.selected {
+                Color::from_rgb(218, 204, 62) // #DACC3E
+            } else {
+                Color::WHITE
+            };
+            let marker = if i == self.selected { "> " } else { "  " };
+            let text = graphics::Text::new(format!("{}{}", marker, label));
+            canvas.draw(&text, DrawParam::default()
+                .dest(Vec2::new(rect.x, rect.y + ITEM_HEIGHT/4.0))
+                .color(color));
+        }
+    }
+}
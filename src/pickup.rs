@@ -1,15 +1,26 @@
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawParam, Rect, Canvas};
 use ggez::glam::Vec2;
-use rand::Rng;
+use crate::collision::{Collider, Shape};
+use crate::powerup::PowerUp;
+use crate::rng::GameRng;
+use crate::tuning::PickupTuning;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PickupType {
     Coin,
     Gem,
+    /// Worth no points, but starts a timed effect on the truck
+    PowerUp(PowerUp),
+    /// Tops up the truck's tank
+    FuelCan,
+    /// Tops up the ink for sculpting the ground
+    InkPot,
 }
 
 pub struct Pickup {
     pub pos: Vec2,
+    prev_pos: Vec2,
     pub width: f32,
     pub height: f32,
     pub pickup_type: PickupType,
@@ -19,83 +30,137 @@ pub struct Pickup {
 }
 
 impl Pickup {
-    pub fn new(x: f32, y: f32, pickup_type: PickupType) -> Self {
-        let (width, height, value, color) = match pickup_type {
-            PickupType::Coin => (
-                30.0, 
-                30.0, 
-                25,
-                Color::from_rgb(218, 204, 62), // #DACC3E
-            ),
-            PickupType::Gem => (
-                40.0, 
-                40.0, 
-                50,
-                Color::from_rgb(0, 255, 127), // #00FF7F
-            ),
-        };
-
-        Pickup {
+    pub fn new(x: f32, y: f32, pickup_type: PickupType, tuning: &PickupTuning) -> Self {
+        let mut pickup = Pickup {
             pos: Vec2::new(x, y),
-            width,
-            height,
+            prev_pos: Vec2::new(x, y),
+            width: 0.0,
+            height: 0.0,
             pickup_type,
             collected: false,
-            value,
-            color,
-        }
+            value: 0,
+            color: Color::WHITE,
+        };
+        pickup.set_type(pickup_type, tuning);
+        pickup
+    }
+
+    /// Turns the pickup into a different kind, keeping its position.
+    pub fn set_type(&mut self, pickup_type: PickupType, tuning: &PickupTuning) {
+        let (width, height, color) = match pickup_type {
+            PickupType::Coin => (30.0, 30.0, Color::from_rgb(218, 204, 62)), // #DACC3E
+            PickupType::Gem => (40.0, 40.0, Color::from_rgb(0, 255, 127)),   // #00FF7F
+            PickupType::PowerUp(power_up) => (28.0, 28.0, power_up.color()),
+            PickupType::FuelCan => (22.0, 30.0, Color::from_rgb(220, 40, 40)),
+            PickupType::InkPot => (26.0, 26.0, Color::from_rgb(40, 60, 200)),
+        };
+        self.width = width;
+        self.height = height;
+        self.color = color;
+        self.pickup_type = pickup_type;
+        self.value = pickup_value(pickup_type, tuning);
+    }
+
+    /// Takes on the score value from a reloaded tuning file.
+    pub fn apply_tuning(&mut self, tuning: &PickupTuning) {
+        self.value = pickup_value(self.pickup_type, tuning);
     }
 
-    pub fn update(&mut self, speed_x: f32, ascent: f32) {
+    pub fn update(&mut self, speed_x: f32) {
+        self.prev_pos = self.pos;
         self.pos.x -= speed_x;
-        self.pos.y += ascent;
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
-        if !self.collected {
-            let pickup_rect = Rect::new(
-                self.pos.x,
-                self.pos.y,
-                self.width,
-                self.height
-            );
+    /// Moves the pickup up to `distance` towards `target`, without overshooting.
+    pub fn pull_towards(&mut self, target: Vec2, distance: f32) {
+        let center = self.pos + self.half_size();
+        let offset = target - center;
+        self.pos += offset.clamp_length_max(distance);
+    }
+
+    /// Distance from the pickup's centre to `point`.
+    pub fn distance_to(&self, point: Vec2) -> f32 {
+        (self.pos + self.half_size()).distance(point)
+    }
 
-            let mesh = graphics::Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::fill(),
-                pickup_rect,
-                self.color,
-            )?;
-            canvas.draw(&mesh, DrawParam::default());
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, alpha: f32) -> GameResult {
+        if !self.collected {
+            let center = self.prev_pos.lerp(self.pos, alpha) + self.half_size();
+            let mesh = match self.pickup_type {
+                PickupType::Coin | PickupType::InkPot => graphics::Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    Vec2::ZERO,
+                    self.width / 2.0,
+                    0.5,
+                    self.color,
+                )?,
+                // Gems are squares stood on a corner; power-ups and fuel cans stand flat
+                PickupType::Gem | PickupType::PowerUp(_) | PickupType::FuelCan => graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    Rect::new(-self.width / 2.0, -self.height / 2.0, self.width, self.height),
+                    self.color,
+                )?,
+            };
+            canvas.draw(&mesh, DrawParam::default()
+                .dest(center)
+                .rotation(self.rotation()));
         }
         Ok(())
     }
 
-    pub fn reposition(&mut self, window_width: f32, window_height: f32) {
-        let mut rng = rand::thread_rng();
-        self.pos.x = window_width + rng.gen_range(0.0..200.0);
-        self.pos.y = rng.gen_range(0.0..window_height);
+    fn half_size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height) / 2.0
+    }
+
+    fn rotation(&self) -> f32 {
+        match self.pickup_type {
+            PickupType::Coin | PickupType::PowerUp(_) | PickupType::FuelCan | PickupType::InkPot => 0.0,
+            PickupType::Gem => std::f32::consts::FRAC_PI_4,
+        }
+    }
+
+    /// Respawns the pickup past the right edge, at a random height on
+    /// screen; `view_top` is the world y at the top of the screen.
+    pub fn reposition(&mut self, playfield_width: f32, view_top: f32, playfield_height: f32, rng: &mut GameRng) {
+        self.pos.x = playfield_width + rng.gen_range(0.0..200.0);
+        self.pos.y = view_top + rng.gen_range(0.0..playfield_height);
         self.collected = false;
+        self.prev_pos = self.pos;
     }
+}
 
-    pub fn collides_with_player(&self, player_pos: Vec2, player_width: f32, player_height: f32) -> bool {
+impl Collider for Pickup {
+    fn shapes(&self) -> Vec<Shape> {
         if self.collected {
-            return false;
+            return Vec::new();
         }
+        let center = self.pos + self.half_size();
+        let shape = match self.pickup_type {
+            PickupType::Coin | PickupType::InkPot => Shape::Circle { center, radius: self.width / 2.0 },
+            PickupType::Gem => Shape::Obb {
+                center,
+                half_extents: self.half_size(),
+                rotation: self.rotation(),
+            },
+            PickupType::PowerUp(_) | PickupType::FuelCan => Shape::Aabb {
+                min: self.pos,
+                max: self.pos + Vec2::new(self.width, self.height),
+            },
+        };
+        vec![shape]
+    }
 
-        let player_left = player_pos.x;
-        let player_right = player_pos.x + player_width;
-        let player_top = player_pos.y - player_height;
-        let player_bottom = player_pos.y;
-        
-        let pickup_left = self.pos.x;
-        let pickup_right = self.pos.x + self.width;
-        let pickup_top = self.pos.y;
-        let pickup_bottom = self.pos.y + self.height;
+    fn motion(&self) -> Vec2 {
+        self.pos - self.prev_pos
+    }
+}
 
-        player_left < pickup_right && 
-        player_right > pickup_left &&
-        player_top < pickup_bottom && 
-        player_bottom > pickup_top
+fn pickup_value(pickup_type: PickupType, tuning: &PickupTuning) -> i32 {
+    match pickup_type {
+        PickupType::Coin => tuning.coin_value,
+        PickupType::Gem => tuning.gem_value,
+        PickupType::PowerUp(_) | PickupType::FuelCan | PickupType::InkPot => 0,
     }
 }
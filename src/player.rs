@@ -1,178 +1,502 @@
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawParam, Rect, Mesh};
 use ggez::glam::Vec2 as GgezVec2;
+use crate::collision::{Collider, Shape};
+use crate::powerup::{Effects, PowerUp};
+use crate::terrain::Terrain;
+use crate::tuning::{DamageTuning, PhysicsTuning, Tuning};
 
+// Truck parts in mesh coordinates, with the truck's top-left corner at the origin
+const BODY_MAIN: Rect = Rect::new(10.0, 6.0, 32.0, 14.0);
+const BODY_FRONT: Rect = Rect::new(0.0, 6.0, 10.0, 10.0);
+const CABIN_TOP: Rect = Rect::new(10.0, 0.0, 20.0, 6.0);
+const WINDOW: Rect = Rect::new(18.0, 2.0, 10.0, 4.0);
+// Where the wheels sit with their springs fully compressed
+const WHEEL_CENTERS: [GgezVec2; 2] = [GgezVec2::new(14.0, 20.0), GgezVec2::new(38.0, 20.0)];
+const WHEEL_RADIUS: f32 = 10.0;
+// Centre of mass in mesh coordinates, low down between the wheels
+const CENTER_OF_MASS: GgezVec2 = GgezVec2::new(24.0, 20.0);
+// What the paint turns into as the truck is wrecked
+const RUST: Color = Color::new(0.45, 0.24, 0.15, 1.0);
+// Seconds between the truck showing and fading while invulnerable
+const BLINK_INTERVAL: f32 = 0.1;
+
+/// The truck, simulated as a rigid chassis of unit mass on two sprung
+/// wheels. Each wheel finds the ground under it on its own; the springs
+/// push the chassis up along the surface normal, which also tips it to
+/// follow the slope. Gravity along the slope, the pedals and rolling drag
+/// act where the wheels touch the ground.
+///
+/// The truck stays at the same x on screen: `velocity.x` is how fast the
+/// world scrolls past it.
 pub struct Player {
+    /// The bottom-centre of the truck with its springs fully compressed
     pub pos: GgezVec2,
+    prev_pos: GgezVec2,
     pub width: f32,
     pub height: f32,
+    /// In px/s
     pub velocity: GgezVec2,
+    /// The chassis' tilt in radians, clockwise
+    pub angle: f32,
+    prev_angle: f32,
+    /// In radians per second
+    pub angular_velocity: f32,
+    /// How far each wheel hangs below its fully compressed position, in px
+    wheel_drop: [f32; 2],
+    prev_wheel_drop: [f32; 2],
+    /// Whether each wheel touched the ground in the last step
+    wheel_contact: [bool; 2],
     pub on_ground: bool,
     pub jump_speed: f32,
-    pub move_speed: f32,
-    last_y: f32,
-    bar_height: f32,
-    prev_bar_height: f32,
-    terrain_positions: [f32; 4],  // Track last 4 terrain positions
-    speed_y: f32,
+    throttle: bool,
+    brake: bool,
+    /// Power-ups currently running
+    pub effects: Effects,
+    /// What's left in the tank. Without fuel the throttle does nothing and
+    /// the truck coasts to a stop.
+    pub fuel: f32,
+    /// The run ends when this reaches zero
+    pub health: f32,
+    max_health: f32,
+    /// Seconds until the truck can be hurt again
+    invulnerable: f32,
 }
 
 impl Player {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new(x: f32, y: f32, speed: f32, tuning: &Tuning) -> Self {
+        let drop = [tuning.physics.suspension_travel; 2];
         Player {
             pos: GgezVec2::new(x, y),
+            prev_pos: GgezVec2::new(x, y),
             width: 48.0,
             height: 30.0,
-            velocity: GgezVec2::ZERO,
+            velocity: GgezVec2::new(speed, 0.0),
+            angle: 0.0,
+            prev_angle: 0.0,
+            angular_velocity: 0.0,
+            wheel_drop: drop,
+            prev_wheel_drop: drop,
+            wheel_contact: [false; 2],
             on_ground: false,
-            jump_speed: -500.0,
-            move_speed: 200.0,
-            last_y: y,
-            bar_height: y,
-            prev_bar_height: y,
-            terrain_positions: [y; 4],
-            speed_y: 1.0,
+            jump_speed: -tuning.physics.jump_speed,
+            throttle: false,
+            brake: false,
+            effects: Effects::default(),
+            fuel: tuning.fuel.capacity,
+            health: tuning.damage.max_health,
+            max_health: tuning.damage.max_health,
+            invulnerable: 0.0,
         }
     }
 
-    pub fn update(&mut self, dt: f32, gravity: f32, speed_x: &mut f32) {
-        self.last_y = self.pos.y;
-        
-        // Update terrain position history
-        for i in (1..4).rev() {
-            self.terrain_positions[i] = self.terrain_positions[i-1];
+    /// Takes on the jump speed from a reloaded tuning file. Everything else
+    /// is read from the tuning on every step.
+    pub fn apply_tuning(&mut self, tuning: &PhysicsTuning) {
+        self.jump_speed = -tuning.jump_speed;
+    }
+
+    /// Takes `amount` off the truck's health and makes it invulnerable for a
+    /// while, unless it still is from the last hit.
+    pub fn take_damage(&mut self, amount: f32, tuning: &DamageTuning) {
+        if self.is_invulnerable() || amount <= 0.0 {
+            return;
         }
-        self.terrain_positions[0] = self.pos.y;
+        self.health = (self.health - amount).max(0.0);
+        self.invulnerable = tuning.invulnerable_time;
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    /// Health left, from 1.0 undamaged down to 0.0.
+    pub fn health_fraction(&self) -> f32 {
+        self.health / self.max_health
+    }
+
+    pub fn is_wrecked(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// How much a boulder hit at `impact_speed` px/s hurts. `normal` points
+    /// from the truck towards the boulder: boulders coming down on the roof
+    /// do more damage and those from underneath less.
+    pub fn impact_damage(&self, impact_speed: f32, normal: GgezVec2, tuning: &DamageTuning) -> f32 {
+        // Direction of the hit relative to the truck, as if it were level
+        let local = GgezVec2::from_angle(-self.angle).rotate(normal);
+        let multiplier = if local.y < -0.7 {
+            tuning.roof_multiplier
+        } else if local.y > 0.7 {
+            tuning.underside_multiplier
+        } else {
+            1.0
+        };
+        (impact_speed * tuning.damage_per_speed).max(tuning.min_damage) * multiplier
+    }
+
+    /// Sets the pedals for the coming steps.
+    pub fn set_pedals(&mut self, throttle: bool, brake: bool) {
+        self.throttle = throttle;
+        self.brake = brake;
+    }
+
+    /// Advances the truck by one step of `dt` seconds over `terrain`, which
+    /// has already scrolled by `terrain_motion` this step.
+    pub fn update(&mut self, dt: f32, tuning: &Tuning, terrain: &Terrain, terrain_motion: GgezVec2) {
+        let fuel = &tuning.fuel;
+        let damage = &tuning.damage;
+        let tuning = &tuning.physics;
+        self.prev_pos = self.pos;
+        self.prev_angle = self.angle;
+        self.prev_wheel_drop = self.wheel_drop;
+        self.effects.update(dt);
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+
+        // Falling speed going into this step, to judge a landing by
+        let was_airborne = !self.on_ground;
+        let fall_speed = self.velocity.y;
 
-        // Apply gravity and terrain following
-        if !self.on_ground {
-            self.velocity.y += gravity;
+        let center_of_mass = self.to_playfield(CENTER_OF_MASS, self.angle);
+        let inertia = self.inertia();
+
+        // Gravity is given as a change in velocity per tick
+        let mut force = GgezVec2::new(0.0, tuning.gravity / dt);
+        let mut torque = 0.0;
+
+        for (i, &mount) in WHEEL_CENTERS.iter().enumerate() {
+            let hub = self.to_playfield(mount, self.angle);
+            let ground = terrain.height_at(hub.x);
+            // Gap between the bottom of the wheel and the ground with the spring fully compressed
+            let gap = ground - (hub.y + WHEEL_RADIUS);
+            let drop = gap.clamp(0.0, tuning.suspension_travel);
+            self.wheel_drop[i] = drop;
+            self.wheel_contact[i] = gap < tuning.suspension_travel;
+            if !self.wheel_contact[i] {
+                continue;
+            }
+
+            let compression = tuning.suspension_travel - drop;
+            let compression_rate = (self.prev_wheel_drop[i] - drop) / dt;
+            let spring = (tuning.suspension_stiffness * compression
+                + tuning.suspension_damping * compression_rate).max(0.0);
+
+            let normal = terrain.normal_at(hub.x);
+            let along = GgezVec2::new(-normal.y, normal.x);  // Forwards along the ground
+            let rolling_speed = self.velocity.dot(along);
+
+            // Each wheel carries half of the drive and drag
+            let mut drive = -tuning.rolling_drag * rolling_speed;
+            if self.throttle && self.fuel > 0.0 {
+                drive += tuning.throttle_accel;
+            }
+            if self.brake {
+                // Don't brake hard enough to roll backwards
+                drive -= tuning.brake_decel.min(rolling_speed.max(0.0) / dt);
+            }
+
+            let wheel_force = normal * spring + along * drive / 2.0;
+            let contact = GgezVec2::new(hub.x, ground);
+            force += wheel_force;
+            torque += (contact - center_of_mass).perp_dot(wheel_force);
         }
-        
-        // Update position
-        self.pos += self.velocity * dt;
-
-        // Adjust speed based on terrain slope
-        if self.prev_bar_height - self.bar_height > self.height {
-            *speed_x -= 0.01 * (self.prev_bar_height - self.bar_height);
-        } else if self.prev_bar_height <= self.bar_height {
-            *speed_x += 0.03 + 0.01 * (self.bar_height - self.prev_bar_height);
+        self.on_ground = self.wheel_contact.contains(&true);
+
+        self.velocity += force * dt;
+        self.angular_velocity += torque / inertia * dt;
+        self.angular_velocity *= (1.0 - tuning.angular_damping * dt).max(0.0);
+        if self.fuel <= 0.0 {
+            self.velocity.x *= (1.0 - fuel.empty_drag * dt).max(0.0);
         }
 
-        // Cap speed
-        if *speed_x < 0.0 {
-            *speed_x = 0.0;
+        // The world only scrolls one way
+        self.velocity.x = self.velocity.x.clamp(0.0, tuning.max_speed / dt);
+
+        self.pos.y += self.velocity.y * dt;
+        self.angle += self.angular_velocity * dt;
+
+        // A fast fall can take a wheel through its whole spring travel in one
+        // step, so the bump stops may be what first meets the ground
+        let falling = self.velocity.y;
+        if self.hit_bump_stops(terrain, terrain_motion) {
+            self.on_ground = true;
         }
-        if *speed_x > 20.0 {
-            *speed_x = 20.0;
+        if was_airborne && self.on_ground {
+            let excess = fall_speed.max(falling) - damage.safe_landing_speed;
+            self.take_damage(excess * damage.landing_damage_per_speed, damage);
         }
+
+        // The engine works harder the further the truck goes and the steeper
+        // it climbs. The distance is how far the ground actually scrolled,
+        // which slow motion shortens
+        let driven = -terrain_motion.x;
+        let climbed = if self.on_ground { (self.prev_pos.y - self.pos.y).max(0.0) } else { 0.0 };
+        let burnt = driven * fuel.burn_per_pixel + climbed * fuel.climb_burn_per_pixel;
+        self.fuel = (self.fuel - burnt).max(0.0);
+    }
+
+    /// Tops up the tank by `amount`, up to `capacity`.
+    pub fn refuel(&mut self, amount: f32, capacity: f32) {
+        self.fuel = (self.fuel + amount).min(capacity);
+    }
+
+    /// Whether the tank is empty and the truck has rolled to a stop.
+    pub fn has_stalled(&self, stall_speed: f32) -> bool {
+        self.fuel <= 0.0 && self.velocity.x < stall_speed
     }
 
-    pub fn set_bar_height(&mut self, height: f32) {
-        self.bar_height = height;
+    /// Stops a wheel from sinking into the ground once its spring is fully
+    /// compressed, by pushing up at that wheel: the chassis rises and turns
+    /// as a rigid body would, and stops moving into the ground. Each wheel's
+    /// path over the scrolling terrain is followed, so it can't skip over a
+    /// bar at speed. Returns whether either wheel hit the ground.
+    fn hit_bump_stops(&mut self, terrain: &Terrain, terrain_motion: GgezVec2) -> bool {
+        let bottom = GgezVec2::new(0.0, WHEEL_RADIUS);
+        let inertia = self.inertia();
+        let mut hit = false;
+        for mount in WHEEL_CENTERS {
+            let from = self.prev_pos
+                + GgezVec2::from_angle(self.prev_angle).rotate(mount - self.anchor())
+                + bottom
+                + terrain_motion;
+            let to = self.to_playfield(mount, self.angle) + bottom;
+            let Some(ground) = terrain.ground_along(from, to, 0.0) else {
+                continue;
+            };
+            let depth = to.y - ground;
+            if depth <= 0.0 {
+                continue;
+            }
+            hit = true;
+
+            // Lever arm of an upward push at the wheel, and how much of the
+            // push goes into lifting rather than turning the chassis
+            let arm = to - self.to_playfield(CENTER_OF_MASS, self.angle);
+            let turn = -arm.x;
+            let share = 1.0 / (1.0 + turn * turn / inertia);
+
+            self.pos.y -= depth * share;
+            self.angle += turn * depth * share / inertia;
+
+            let point_velocity = self.velocity + self.angular_velocity * arm.perp();
+            if point_velocity.y > 0.0 {
+                let impulse = point_velocity.y * share;
+                self.velocity.y -= impulse;
+                self.angular_velocity += turn * impulse / inertia;
+            }
+        }
+        hit
     }
 
-    pub fn set_prev_bar_height(&mut self, height: f32) {
-        self.prev_bar_height = height;
+    /// Moment of inertia of a box the truck's size, per unit mass.
+    fn inertia(&self) -> f32 {
+        (self.width * self.width + self.height * self.height) / 12.0
     }
 
-    pub fn jump(&mut self) {
+    /// Leaves the ground at the jump speed times `boost`.
+    pub fn jump(&mut self, boost: f32) {
         if self.on_ground {
-            self.velocity.y = self.jump_speed;
+            self.velocity.y = self.jump_speed * boost;
             self.on_ground = false;
         }
     }
 
-    pub fn move_left(&mut self) {
-        self.velocity.x = -self.move_speed;
+    /// Whether the truck has dropped through the ground under it, further
+    /// than it is tall.
+    pub fn has_fallen(&self, terrain: &Terrain) -> bool {
+        self.pos.y - terrain.height_at(self.pos.x) > self.height
     }
 
-    pub fn move_right(&mut self) {
-        self.velocity.x = self.move_speed;
+    /// Whether the truck has rolled onto its roof.
+    pub fn has_flipped(&self) -> bool {
+        self.angle.abs() > std::f32::consts::FRAC_PI_2
     }
 
-    pub fn stop_horizontal(&mut self) {
-        self.velocity.x = 0.0;
+    /// The truck's tilt in radians. Drawing and collision both rotate the
+    /// truck by this around `pos`.
+    pub fn rotation(&self) -> f32 {
+        self.angle
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
-        // Calculate rotation based on vertical movement
-        // Calculate averages for rotation
-        let avg0 = (self.terrain_positions[0..3].iter().sum::<f32>()) / 3.0;
-        let avg1 = (self.terrain_positions[1..4].iter().sum::<f32>()) / 3.0;
-        
-        let rotation = if self.pos.y > self.last_y {
-            -2.0 * std::f32::consts::PI / (self.pos.y / self.last_y)
-        } else {
-            -2.0 * std::f32::consts::PI / (avg0 / avg1)
-        };
+    /// Where each wheel's centre is in mesh coordinates, after its spring.
+    fn wheel_centers(wheel_drop: [f32; 2]) -> [GgezVec2; 2] {
+        [0, 1].map(|i| WHEEL_CENTERS[i] + GgezVec2::new(0.0, wheel_drop[i]))
+    }
 
-        // Colors from the Processing version
-        let body_color = Color::from_rgb(127, 183, 190);  // #7FB7BE
+    /// The mesh-space point the truck is anchored and rotated around.
+    fn anchor(&self) -> GgezVec2 {
+        GgezVec2::new(self.width / 2.0, self.height)
+    }
+
+    /// Converts a point in mesh coordinates to playfield coordinates.
+    fn to_playfield(&self, local: GgezVec2, rotation: f32) -> GgezVec2 {
+        self.pos + GgezVec2::from_angle(rotation).rotate(local - self.anchor())
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, alpha: f32) -> GameResult {
+        // Colors from the Processing version; the paint rusts as the truck takes damage
+        let wear = 1.0 - self.health_fraction();
+        let body_color = mix(Color::from_rgb(127, 183, 190), RUST, wear * 0.7);  // #7FB7BE
         let wheel_color = Color::from_rgb(218, 204, 62);  // #DACC3E
         let window_color = Color::from_rgb(211, 243, 238); // #D3F3EE
 
-        let draw_param = DrawParam::default()
-            .dest(self.pos)
-            .rotation(rotation)
-            .offset(GgezVec2::new(0.5, 1.0));  // Change y offset to 1.0 to use bottom as anchor
+        let mut draw_param = DrawParam::default()
+            .dest(self.prev_pos.lerp(self.pos, alpha))
+            .rotation(self.prev_angle + (self.angle - self.prev_angle) * alpha)
+            // Mesh offsets are in pixels, not fractions of the size
+            .offset(self.anchor());  // Bottom-centre anchor
+
+        // Blink while invulnerable after a hit
+        if (self.invulnerable / BLINK_INTERVAL) as i32 % 2 == 1 {
+            draw_param = draw_param.color(Color::new(1.0, 1.0, 1.0, 0.3));
+        }
 
         // Draw body parts
-        let body_main = Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            Rect::new(10.0, 6.0, 32.0, 14.0),
-            body_color,
-        )?;
-        
-        let body_front = Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            Rect::new(0.0, 6.0, 10.0, 10.0),
-            body_color,
-        )?;
-
-        let cabin_top = Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            Rect::new(10.0, 0.0, 20.0, 6.0),
-            body_color,
-        )?;
-
-        let window = Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            Rect::new(18.0, 2.0, 10.0, 4.0),
-            window_color,
-        )?;
+        for (rect, color) in [
+            (BODY_MAIN, body_color),
+            (BODY_FRONT, body_color),
+            (CABIN_TOP, body_color),
+            (WINDOW, window_color),
+        ] {
+            let mesh = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, color)?;
+            canvas.draw(&mesh, draw_param);
+        }
 
         // Draw wheels (circles)
-        let wheel1 = Mesh::new_circle(
-            ctx,
-            graphics::DrawMode::fill(),
-            GgezVec2::new(14.0, 20.0),
-            10.0,
-            0.1,
-            wheel_color,
-        )?;
-
-        let wheel2 = Mesh::new_circle(
-            ctx,
-            graphics::DrawMode::fill(),
-            GgezVec2::new(38.0, 20.0),
-            10.0,
-            0.1,
-            wheel_color,
-        )?;
-
-        // Draw all parts
-        canvas.draw(&body_main, draw_param);
-        canvas.draw(&body_front, draw_param);
-        canvas.draw(&cabin_top, draw_param);
-        canvas.draw(&window, draw_param);
-        canvas.draw(&wheel1, draw_param);
-        canvas.draw(&wheel2, draw_param);
+        let wheel_drop = [0, 1].map(|i| {
+            self.prev_wheel_drop[i] + (self.wheel_drop[i] - self.prev_wheel_drop[i]) * alpha
+        });
+        for center in Self::wheel_centers(wheel_drop) {
+            let wheel = Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::fill(),
+                center,
+                WHEEL_RADIUS,
+                0.1,
+                wheel_color,
+            )?;
+            canvas.draw(&wheel, draw_param);
+        }
+
+        self.draw_damage(ctx, canvas, draw_param)?;
+
+        if self.effects.is_active(PowerUp::Shield) {
+            let mut color = PowerUp::Shield.color();
+            color.a = 0.6;
+            let bubble = Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::stroke(2.0),
+                CENTER_OF_MASS - GgezVec2::new(0.0, self.height / 4.0),
+                self.width * 0.7,
+                0.5,
+                color,
+            )?;
+            canvas.draw(&bubble, draw_param);
+        }
 
         Ok(())
     }
+
+    /// A cracked window once the truck is a third of the way wrecked, then a
+    /// dented door and smoke from the engine.
+    fn draw_damage(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, draw_param: DrawParam) -> GameResult {
+        let health = self.health_fraction();
+        if health < 2.0 / 3.0 {
+            let crack = Mesh::new_line(
+                ctx,
+                &[
+                    GgezVec2::new(WINDOW.x + 2.0, WINDOW.y),
+                    GgezVec2::new(WINDOW.x + 5.0, WINDOW.y + 2.5),
+                    GgezVec2::new(WINDOW.x + 4.0, WINDOW.y + WINDOW.h),
+                    GgezVec2::new(WINDOW.x + 5.0, WINDOW.y + 2.5),
+                    GgezVec2::new(WINDOW.right(), WINDOW.y + 1.0),
+                ],
+                0.75,
+                Color::from_rgb(40, 40, 40),
+            )?;
+            canvas.draw(&crack, draw_param);
+        }
+        if health < 1.0 / 3.0 {
+            let dent = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Rect::new(BODY_MAIN.x + 8.0, BODY_MAIN.y + 3.0, 9.0, 5.0),
+                Color::new(0.0, 0.0, 0.0, 0.35),
+            )?;
+            canvas.draw(&dent, draw_param);
+
+            for (center, radius) in [(GgezVec2::new(4.0, 2.0), 3.5), (GgezVec2::new(1.0, -4.0), 5.0)] {
+                let smoke = Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    center,
+                    radius,
+                    0.5,
+                    Color::new(0.5, 0.5, 0.5, 0.6),
+                )?;
+                canvas.draw(&smoke, draw_param);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let amount = amount.clamp(0.0, 1.0);
+    Color::new(
+        from.r + (to.r - from.r) * amount,
+        from.g + (to.g - from.g) * amount,
+        from.b + (to.b - from.b) * amount,
+        from.a + (to.a - from.a) * amount,
+    )
+}
+
+impl Collider for Player {
+    /// The body panels and both wheels, tilted the same way the truck is drawn.
+    fn shapes(&self) -> Vec<Shape> {
+        let rotation = self.rotation();
+        let body = [BODY_MAIN, BODY_FRONT, CABIN_TOP].map(|rect| Shape::Obb {
+            center: self.to_playfield(GgezVec2::from(rect.center()), rotation),
+            half_extents: GgezVec2::new(rect.w, rect.h) / 2.0,
+            rotation,
+        });
+        let wheels = Self::wheel_centers(self.wheel_drop).map(|center| Shape::Circle {
+            center: self.to_playfield(center, rotation),
+            radius: WHEEL_RADIUS,
+        });
+        body.into_iter().chain(wheels).collect()
+    }
+
+    fn motion(&self) -> GgezVec2 {
+        self.pos - self.prev_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::TerrainStyle;
+    use crate::TICK_DT;
+
+    /// Health lost by a truck dropped level onto flat ground at `fall_speed` px/s.
+    fn landing_damage(fall_speed: f32) -> f32 {
+        let tuning = Tuning::default();
+        let ground = 400.0;
+        let terrain = Terrain::new(640.0, ground, TerrainStyle::Blocky);
+        let mut player = Player::new(160.0, ground - 100.0, 0.0, &tuning);
+        player.velocity.y = fall_speed;
+        for _ in 0..60 {
+            player.update(TICK_DT, &tuning, &terrain, GgezVec2::ZERO);
+        }
+        assert!(player.on_ground);
+        tuning.damage.max_health - player.health
+    }
+
+    #[test]
+    fn harder_landings_do_more_damage() {
+        assert_eq!(landing_damage(300.0), 0.0);
+        let damage = [740.0, 900.0, 1040.0, 1200.0].map(landing_damage);
+        assert!(damage[0] > 0.0, "{:?}", damage);
+        assert!(damage.windows(2).all(|pair| pair[1] > pair[0]), "{:?}", damage);
+    }
 }
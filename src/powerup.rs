@@ -0,0 +1,242 @@
+use ggez::graphics::Color;
+
+use crate::rng::GameRng;
+use crate::tuning::PowerUpTuning;
+
+/// Timed effects the truck can pick up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUp {
+    /// Absorbs the next boulder hit
+    Shield,
+    /// Pulls nearby pickups towards the truck
+    Magnet,
+    /// Slows the scrolling of the world
+    SlowMotion,
+    /// Makes jumps higher
+    SuperJump,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 4] = [PowerUp::Shield, PowerUp::Magnet, PowerUp::SlowMotion, PowerUp::SuperJump];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUp::Shield => "Shield",
+            PowerUp::Magnet => "Magnet",
+            PowerUp::SlowMotion => "Slow-mo",
+            PowerUp::SuperJump => "Super jump",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUp::Shield => Color::from_rgb(80, 160, 255),
+            PowerUp::Magnet => Color::from_rgb(230, 60, 60),
+            PowerUp::SlowMotion => Color::from_rgb(180, 110, 255),
+            PowerUp::SuperJump => Color::from_rgb(255, 150, 30),
+        }
+    }
+
+    /// How long the effect lasts, in seconds.
+    pub fn duration(self, tuning: &PowerUpTuning) -> f32 {
+        match self {
+            PowerUp::Shield => tuning.shield_duration,
+            PowerUp::Magnet => tuning.magnet_duration,
+            PowerUp::SlowMotion => tuning.slow_motion_duration,
+            PowerUp::SuperJump => tuning.super_jump_duration,
+        }
+    }
+
+    fn weight(self, tuning: &PowerUpTuning) -> f32 {
+        match self {
+            PowerUp::Shield => tuning.shield_weight,
+            PowerUp::Magnet => tuning.magnet_weight,
+            PowerUp::SlowMotion => tuning.slow_motion_weight,
+            PowerUp::SuperJump => tuning.super_jump_weight,
+        }
+    }
+
+    /// Picks a power-up at random, in proportion to the spawn weights.
+    /// Returns `None` if every weight is zero.
+    pub fn choose(tuning: &PowerUpTuning, rng: &mut GameRng) -> Option<PowerUp> {
+        let total: f32 = Self::ALL.iter().map(|p| p.weight(tuning)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut roll = rng.gen_f32() * total;
+        let mut chosen = None;
+        for power_up in Self::ALL {
+            let weight = power_up.weight(tuning);
+            if weight <= 0.0 {
+                continue;
+            }
+            chosen = Some(power_up);
+            if roll < weight {
+                break;
+            }
+            roll -= weight;
+        }
+        chosen
+    }
+}
+
+/// A power-up that is running, with the seconds it has left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActiveEffect {
+    pub power_up: PowerUp,
+    pub remaining: f32,
+    /// The time it had when last collected, for drawing how much is left
+    pub duration: f32,
+}
+
+/// The power-ups currently affecting the truck, in the order they were collected.
+///
+/// Collecting one that is already running adds its duration to the time it
+/// has left, up to `max_stacked_duration`. A shield can only ever absorb one
+/// hit, so collecting another just restarts its timer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Effects {
+    active: Vec<ActiveEffect>,
+}
+
+impl Effects {
+    pub fn activate(&mut self, power_up: PowerUp, tuning: &PowerUpTuning) {
+        let duration = power_up.duration(tuning);
+        match self.active.iter_mut().find(|e| e.power_up == power_up) {
+            Some(effect) if power_up == PowerUp::Shield => {
+                effect.remaining = duration;
+                effect.duration = duration;
+            },
+            Some(effect) => {
+                effect.remaining = (effect.remaining + duration).min(tuning.max_stacked_duration.max(duration));
+                effect.duration = effect.remaining;
+            },
+            None => self.active.push(ActiveEffect { power_up, remaining: duration, duration }),
+        }
+    }
+
+    /// Counts down every effect by `dt` seconds, dropping those that ran out.
+    pub fn update(&mut self, dt: f32) {
+        for effect in &mut self.active {
+            effect.remaining -= dt;
+        }
+        self.active.retain(|e| e.remaining > 0.0);
+    }
+
+    pub fn is_active(&self, power_up: PowerUp) -> bool {
+        self.active.iter().any(|e| e.power_up == power_up)
+    }
+
+    /// Ends `power_up` early, returning whether it was running.
+    pub fn consume(&mut self, power_up: PowerUp) -> bool {
+        let before = self.active.len();
+        self.active.retain(|e| e.power_up != power_up);
+        self.active.len() != before
+    }
+
+    pub fn active(&self) -> &[ActiveEffect] {
+        &self.active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remaining(effects: &Effects, power_up: PowerUp) -> Option<f32> {
+        effects.active().iter().find(|e| e.power_up == power_up).map(|e| e.remaining)
+    }
+
+    #[test]
+    fn durations_stack_up_to_the_cap() {
+        let tuning = PowerUpTuning {
+            magnet_duration: 4.0,
+            max_stacked_duration: 10.0,
+            ..PowerUpTuning::default()
+        };
+        let mut effects = Effects::default();
+        effects.activate(PowerUp::Magnet, &tuning);
+        effects.update(1.0);
+        effects.activate(PowerUp::Magnet, &tuning);
+        assert_eq!(remaining(&effects, PowerUp::Magnet), Some(7.0));
+        effects.activate(PowerUp::Magnet, &tuning);
+        assert_eq!(remaining(&effects, PowerUp::Magnet), Some(10.0));
+        assert_eq!(effects.active().len(), 1);
+    }
+
+    #[test]
+    fn one_pickup_lasts_its_full_duration_even_past_the_cap() {
+        let tuning = PowerUpTuning {
+            slow_motion_duration: 12.0,
+            max_stacked_duration: 10.0,
+            ..PowerUpTuning::default()
+        };
+        let mut effects = Effects::default();
+        effects.activate(PowerUp::SlowMotion, &tuning);
+        effects.activate(PowerUp::SlowMotion, &tuning);
+        assert_eq!(remaining(&effects, PowerUp::SlowMotion), Some(12.0));
+    }
+
+    #[test]
+    fn another_shield_restarts_instead_of_stacking() {
+        let tuning = PowerUpTuning {
+            shield_duration: 5.0,
+            max_stacked_duration: 20.0,
+            ..PowerUpTuning::default()
+        };
+        let mut effects = Effects::default();
+        effects.activate(PowerUp::Shield, &tuning);
+        effects.update(2.0);
+        effects.activate(PowerUp::Shield, &tuning);
+        assert_eq!(remaining(&effects, PowerUp::Shield), Some(5.0));
+        assert!(effects.consume(PowerUp::Shield));
+        assert!(!effects.is_active(PowerUp::Shield));
+        assert!(!effects.consume(PowerUp::Shield));
+    }
+
+    #[test]
+    fn effects_run_out() {
+        let tuning = PowerUpTuning::default();
+        let mut effects = Effects::default();
+        effects.activate(PowerUp::SuperJump, &tuning);
+        effects.update(tuning.super_jump_duration - 0.01);
+        assert!(effects.is_active(PowerUp::SuperJump));
+        effects.update(0.02);
+        assert!(!effects.is_active(PowerUp::SuperJump));
+    }
+
+    #[test]
+    fn choices_follow_the_spawn_weights() {
+        let tuning = PowerUpTuning {
+            shield_weight: 1.0,
+            magnet_weight: 2.0,
+            slow_motion_weight: 0.0,
+            super_jump_weight: 5.0,
+            ..PowerUpTuning::default()
+        };
+        let mut rng = GameRng::new(11);
+        let rolls = 80_000;
+        let mut counts = [0; 4];
+        for _ in 0..rolls {
+            let chosen = PowerUp::choose(&tuning, &mut rng).unwrap();
+            counts[PowerUp::ALL.iter().position(|&p| p == chosen).unwrap()] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        for (count, weight) in counts.into_iter().zip([1.0, 2.0, 0.0, 5.0]) {
+            let share = count as f32 / rolls as f32;
+            assert!((share - weight / 8.0).abs() < 0.01, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn nothing_is_chosen_without_weights() {
+        let tuning = PowerUpTuning {
+            shield_weight: 0.0,
+            magnet_weight: 0.0,
+            slow_motion_weight: 0.0,
+            super_jump_weight: 0.0,
+            ..PowerUpTuning::default()
+        };
+        assert_eq!(PowerUp::choose(&tuning, &mut GameRng::new(1)), None);
+    }
+}
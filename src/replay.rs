@@ -0,0 +1,394 @@
+use std::io::{self, Read, Write};
+
+use ggez::glam::Vec2;
+
+use crate::terrain::{BrushMode, TerrainStyle};
+use crate::tuning::Tuning;
+use crate::world::FrameInput;
+
+const MAGIC: &[u8; 4] = b"HMTR";
+const VERSION: u8 = 7;
+// Far more than any tuning file needs, so a corrupt length can't ask for
+// gigabytes
+const MAX_TUNING_LENGTH: usize = 64 * 1024;
+
+// Bits of the per-tick key byte
+const JUMP: u8 = 1 << 0;
+const THROTTLE: u8 = 1 << 1;
+const BRAKE: u8 = 1 << 2;
+// The brush mode takes the two bits above the keys
+const BRUSH_SHIFT: u8 = 3;
+const BRUSH_MASK: u8 = 0b11 << BRUSH_SHIFT;
+// One-shot presses of the A/S keys
+const SPEED_UP: u8 = 1 << 5;
+const SLOW_DOWN: u8 = 1 << 6;
+
+/// A recorded run: the seed, terrain style and tuning plus the input of
+/// every simulation tick, and any tuning reloaded along the way. Feeding the
+/// inputs back into a `World` built from the same seed, style and tuning,
+/// and applying each tuning change before its tick, reproduces the run exactly.
+///
+/// On disk, identical consecutive inputs are stored once with a repeat count:
+/// `"HMTR"`, version byte, seed (u64), terrain style byte, run count (u32), then per run the repeat
+/// count (u16), mouse x and y (f32) and the key byte, which also holds the
+/// brush mode and the A/S presses. After the runs come the starting tuning, the number of tuning
+/// changes (u32) and per change its tick (u32) and tuning. Each tuning is
+/// stored as its length in bytes (u32) and TOML text. Integers and floats
+/// are little-endian.
+pub struct Replay {
+    pub seed: u64,
+    pub terrain_style: TerrainStyle,
+    /// What the run started with
+    pub tuning: Tuning,
+    /// Tuning reloaded during the run, with the tick it took effect on
+    pub tuning_changes: Vec<(usize, Tuning)>,
+    pub inputs: Vec<FrameInput>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, terrain_style: TerrainStyle, tuning: &Tuning) -> Self {
+        Replay {
+            seed,
+            terrain_style,
+            tuning: tuning.clone(),
+            tuning_changes: Vec::new(),
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, input: FrameInput) {
+        self.inputs.push(input);
+    }
+
+    /// Records `tuning` as taking effect from the next tick recorded.
+    pub fn record_tuning(&mut self, tuning: &Tuning) {
+        let tick = self.inputs.len();
+        // Only the last of several reloads between two ticks matters
+        self.tuning_changes.retain(|(t, _)| *t != tick);
+        self.tuning_changes.push((tick, tuning.clone()));
+    }
+
+    /// The tuning that takes effect just before `tick`, if it changed then.
+    pub fn tuning_change_at(&self, tick: usize) -> Option<&Tuning> {
+        self.tuning_changes.iter().find(|(t, _)| *t == tick).map(|(_, tuning)| tuning)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut runs: Vec<(u16, FrameInput)> = Vec::new();
+        for input in &self.inputs {
+            match runs.last_mut() {
+                Some((count, last)) if last == input && *count < u16::MAX => *count += 1,
+                _ => runs.push((1, *input)),
+            }
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        writer.write_all(&[encode_terrain_style(self.terrain_style)])?;
+        writer.write_all(&(runs.len() as u32).to_le_bytes())?;
+        for (count, input) in runs {
+            writer.write_all(&count.to_le_bytes())?;
+            writer.write_all(&input.mouse_pos.x.to_le_bytes())?;
+            writer.write_all(&input.mouse_pos.y.to_le_bytes())?;
+            writer.write_all(&[encode_keys(&input)])?;
+        }
+
+        write_tuning(writer, &self.tuning)?;
+        writer.write_all(&(self.tuning_changes.len() as u32).to_le_bytes())?;
+        for (tick, tuning) in &self.tuning_changes {
+            writer.write_all(&(*tick as u32).to_le_bytes())?;
+            write_tuning(writer, tuning)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Replay> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a replay file".to_string()));
+        }
+        let version = read_array::<1, _>(reader)?[0];
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported replay version {}", version)));
+        }
+
+        let seed = u64::from_le_bytes(read_array(reader)?);
+        let style_byte = read_array::<1, _>(reader)?[0];
+        let terrain_style = decode_terrain_style(style_byte).ok_or_else(|| {
+            invalid_data(format!("unknown terrain style {}", style_byte))
+        })?;
+        let run_count = u32::from_le_bytes(read_array(reader)?);
+        let mut inputs = Vec::new();
+        for _ in 0..run_count {
+            let count = u16::from_le_bytes(read_array(reader)?);
+            let x = f32::from_le_bytes(read_array(reader)?);
+            let y = f32::from_le_bytes(read_array(reader)?);
+            let keys = read_array::<1, _>(reader)?[0];
+            let input = decode_input(Vec2::new(x, y), keys);
+            inputs.extend(std::iter::repeat_n(input, count as usize));
+        }
+
+        let tuning = read_tuning(reader)?;
+        let change_count = u32::from_le_bytes(read_array(reader)?);
+        let mut tuning_changes = Vec::new();
+        for _ in 0..change_count {
+            let tick = u32::from_le_bytes(read_array(reader)?) as usize;
+            tuning_changes.push((tick, read_tuning(reader)?));
+        }
+
+        Ok(Replay { seed, terrain_style, tuning, tuning_changes, inputs })
+    }
+}
+
+fn encode_keys(input: &FrameInput) -> u8 {
+    let mut keys = 0;
+    for (pressed, bit) in [
+        (input.jump, JUMP),
+        (input.throttle, THROTTLE),
+        (input.brake, BRAKE),
+        (input.speed_up, SPEED_UP),
+        (input.slow_down, SLOW_DOWN),
+    ] {
+        if pressed {
+            keys |= bit;
+        }
+    }
+    keys | encode_brush_mode(input.brush) << BRUSH_SHIFT
+}
+
+fn decode_input(mouse_pos: Vec2, keys: u8) -> FrameInput {
+    FrameInput {
+        mouse_pos,
+        jump: keys & JUMP != 0,
+        throttle: keys & THROTTLE != 0,
+        brake: keys & BRAKE != 0,
+        speed_up: keys & SPEED_UP != 0,
+        slow_down: keys & SLOW_DOWN != 0,
+        brush: decode_brush_mode((keys & BRUSH_MASK) >> BRUSH_SHIFT),
+    }
+}
+
+fn encode_brush_mode(mode: BrushMode) -> u8 {
+    match mode {
+        BrushMode::Flatten => 0,
+        BrushMode::Raise => 1,
+        BrushMode::Lower => 2,
+        BrushMode::Smooth => 3,
+    }
+}
+
+// Every two-bit value is a mode, so this can't fail
+fn decode_brush_mode(bits: u8) -> BrushMode {
+    match bits {
+        0 => BrushMode::Flatten,
+        1 => BrushMode::Raise,
+        2 => BrushMode::Lower,
+        _ => BrushMode::Smooth,
+    }
+}
+
+fn encode_terrain_style(style: TerrainStyle) -> u8 {
+    match style {
+        TerrainStyle::Blocky => 0,
+        TerrainStyle::Linear => 1,
+        TerrainStyle::CatmullRom => 2,
+    }
+}
+
+fn decode_terrain_style(byte: u8) -> Option<TerrainStyle> {
+    TerrainStyle::ALL.into_iter().find(|&style| encode_terrain_style(style) == byte)
+}
+
+fn write_tuning<W: Write>(writer: &mut W, tuning: &Tuning) -> io::Result<()> {
+    let source = toml::to_string(tuning).map_err(|e| invalid_data(e.to_string()))?;
+    writer.write_all(&(source.len() as u32).to_le_bytes())?;
+    writer.write_all(source.as_bytes())
+}
+
+fn read_tuning<R: Read>(reader: &mut R) -> io::Result<Tuning> {
+    let length = u32::from_le_bytes(read_array(reader)?) as usize;
+    if length > MAX_TUNING_LENGTH {
+        return Err(invalid_data(format!("tuning in replay is {} bytes long", length)));
+    }
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    let source = String::from_utf8(bytes).map_err(|e| invalid_data(e.to_string()))?;
+    Tuning::from_toml(&source).map_err(|e| invalid_data(format!("invalid tuning in replay: {}", e)))
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Where header fields start: after the magic, version byte and seed
+    const STYLE_OFFSET: usize = 4 + 1 + 8;
+    const RUN_COUNT_OFFSET: usize = STYLE_OFFSET + 1;
+
+    fn round_trip(replay: &Replay) -> Replay {
+        let mut bytes = Vec::new();
+        replay.write_to(&mut bytes).unwrap();
+        Replay::read_from(&mut bytes.as_slice()).unwrap()
+    }
+
+    fn input(x: f32, keys: u8, brush: BrushMode) -> FrameInput {
+        decode_input(Vec2::new(x, -x), keys & !BRUSH_MASK | encode_brush_mode(brush) << BRUSH_SHIFT)
+    }
+
+    #[test]
+    fn every_input_survives_a_round_trip() {
+        for style in TerrainStyle::ALL {
+            let mut replay = Replay::new(0xDEAD_BEEF_0123_4567, style, &Tuning::default());
+            for (i, brush) in [BrushMode::Raise, BrushMode::Lower, BrushMode::Flatten, BrushMode::Smooth]
+                .into_iter()
+                .enumerate()
+            {
+                for keys in [0, JUMP, THROTTLE, BRAKE, SPEED_UP, SLOW_DOWN, JUMP | BRAKE | SLOW_DOWN, JUMP | THROTTLE | BRAKE | SPEED_UP | SLOW_DOWN] {
+                    replay.record(input(i as f32 * 10.5 + keys as f32, keys, brush));
+                }
+            }
+            let read = round_trip(&replay);
+            assert_eq!(read.seed, replay.seed);
+            assert_eq!(read.terrain_style, style);
+            assert_eq!(read.inputs, replay.inputs);
+        }
+    }
+
+    #[test]
+    fn runs_longer_than_a_repeat_count_are_split() {
+        let mut replay = Replay::new(1, TerrainStyle::Blocky, &Tuning::default());
+        let held = input(3.0, THROTTLE, BrushMode::Raise);
+        replay.inputs = vec![held; u16::MAX as usize * 2 + 5];
+        replay.record(input(4.0, JUMP, BrushMode::Smooth));
+        replay.record(held);
+
+        let mut bytes = Vec::new();
+        replay.write_to(&mut bytes).unwrap();
+        // Three runs of the held input, then the other two
+        let run_count = u32::from_le_bytes(bytes[RUN_COUNT_OFFSET..RUN_COUNT_OFFSET + 4].try_into().unwrap());
+        assert_eq!(run_count, 5);
+        assert_eq!(round_trip(&replay).inputs, replay.inputs);
+    }
+
+    #[test]
+    fn tuning_survives_a_round_trip() {
+        let mut tuning = Tuning::default();
+        tuning.physics.gravity = 0.1 + 0.2;
+        tuning.fuel.burn_per_pixel = f32::MIN_POSITIVE;
+        let mut replay = Replay::new(1, TerrainStyle::Linear, &tuning);
+        replay.record(input(1.0, 0, BrushMode::Flatten));
+        tuning.boulders.restitution = 0.9;
+        replay.record_tuning(&tuning);
+        // A second reload before the next tick replaces the first
+        tuning.boulders.restitution = 0.7;
+        replay.record_tuning(&tuning);
+        replay.record(input(2.0, 0, BrushMode::Flatten));
+
+        let read = round_trip(&replay);
+        assert_eq!(read.tuning, replay.tuning);
+        assert_eq!(read.tuning_changes.len(), 1);
+        assert_eq!(read.tuning_change_at(1), Some(&tuning));
+        assert_eq!(read.tuning_change_at(0), None);
+    }
+
+    #[test]
+    fn a_run_with_a_tuning_reload_plays_back_the_same() {
+        use crate::world::World;
+        use crate::TICK_DT;
+
+        let input_at = |tick: usize| input(tick as f32 % 640.0, THROTTLE, BrushMode::Flatten);
+        let mut tuning = Tuning::default();
+        let mut world = World::new(5, TerrainStyle::Blocky, &tuning);
+        let mut replay = Replay::new(5, TerrainStyle::Blocky, &tuning);
+        for tick in 0..300 {
+            if tick == 120 {
+                tuning.physics.gravity *= 1.5;
+                world.apply_tuning(&tuning);
+                replay.record_tuning(&tuning);
+            }
+            let input = input_at(tick);
+            replay.record(input);
+            world.step(&input, TICK_DT);
+        }
+
+        let read = round_trip(&replay);
+        let mut played = World::new(read.seed, read.terrain_style, &read.tuning);
+        for (tick, input) in read.inputs.iter().enumerate() {
+            if let Some(tuning) = read.tuning_change_at(tick) {
+                played.apply_tuning(tuning);
+            }
+            played.step(input, TICK_DT);
+        }
+        assert_eq!(played.player.pos, world.player.pos);
+        assert_eq!(played.score, world.score);
+        assert_eq!(played.stats, world.stats);
+        assert_eq!(played.tuning, world.tuning);
+    }
+
+    #[test]
+    fn an_empty_replay_survives_a_round_trip() {
+        let replay = Replay::new(u64::MAX, TerrainStyle::CatmullRom, &Tuning::default());
+        assert!(round_trip(&replay).inputs.is_empty());
+    }
+
+    #[test]
+    fn bad_magic_is_rejected() {
+        let mut bytes = Vec::new();
+        Replay::new(1, TerrainStyle::Blocky, &Tuning::default()).write_to(&mut bytes).unwrap();
+        bytes[0] = b'X';
+        let error = Replay::read_from(&mut bytes.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        for version in [VERSION - 1, VERSION + 1] {
+            let mut bytes = Vec::new();
+            Replay::new(1, TerrainStyle::Blocky, &Tuning::default()).write_to(&mut bytes).unwrap();
+            bytes[4] = version;
+            let error = Replay::read_from(&mut bytes.as_slice()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn unknown_terrain_styles_are_rejected() {
+        let mut bytes = Vec::new();
+        Replay::new(1, TerrainStyle::Blocky, &Tuning::default()).write_to(&mut bytes).unwrap();
+        bytes[STYLE_OFFSET] = 0xFF;
+        assert!(Replay::read_from(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let mut replay = Replay::new(1, TerrainStyle::Blocky, &Tuning::default());
+        replay.record(input(1.0, JUMP, BrushMode::Lower));
+        let mut bytes = Vec::new();
+        replay.write_to(&mut bytes).unwrap();
+        bytes.pop();
+        let error = Replay::read_from(&mut bytes.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_tuning_lengths_are_rejected() {
+        let mut bytes = Vec::new();
+        Replay::new(1, TerrainStyle::Blocky, &Tuning::default()).write_to(&mut bytes).unwrap();
+        // No runs, so the tuning length follows the run count
+        let length_offset = RUN_COUNT_OFFSET + 4;
+        bytes[length_offset..length_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = Replay::read_from(&mut bytes.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
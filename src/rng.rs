@@ -0,0 +1,36 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::distributions::uniform::{SampleRange, SampleUniform};
+
+/// The one source of randomness for a run. Every spawner draws from it, so
+/// the same seed always produces the same world.
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn gen_range<T, R>(&mut self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.rng.gen_range(range)
+    }
+
+    /// Returns a value in `0.0..1.0`.
+    pub fn gen_f32(&mut self) -> f32 {
+        self.rng.gen::<f32>()
+    }
+}
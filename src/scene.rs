@@ -0,0 +1,31 @@
+/// The screens the game can be on. `GameState` owns the current scene and
+/// moves between them through `GameState::set_scene`, which runs the exit
+/// hook of the old scene and the enter hook of the new one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scene {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+    HighScores,
+}
+
+impl Scene {
+    /// Whether the game may move from this scene to `next`.
+    pub fn can_transition_to(self, next: Scene) -> bool {
+        matches!(
+            (self, next),
+            (Scene::Title, Scene::Playing)
+                | (Scene::Playing, Scene::GameOver)
+                | (Scene::Playing, Scene::Paused)
+                | (Scene::Playing, Scene::Title)
+                | (Scene::Paused, Scene::Playing)
+                | (Scene::Paused, Scene::Title)
+                | (Scene::GameOver, Scene::Title)
+                | (Scene::GameOver, Scene::Playing)
+                | (Scene::GameOver, Scene::HighScores)
+                | (Scene::Title, Scene::HighScores)
+                | (Scene::HighScores, Scene::Title)
+        )
+    }
+}
@@ -1,134 +1,562 @@
 use ggez::{Context, GameResult};
 use ggez::graphics::{self, Color, DrawParam, Rect};
 use ggez::glam::Vec2;
-use crate::player::Player;
-use crate::WINDOW_WIDTH;
+use serde::{Deserialize, Serialize};
 
-pub struct Bar {
-    pub pos: Vec2,
-    pub width: f32,
-    pub height: f32,
-    speed: f32,
-    ascent: f32,
+use crate::collision::Contact;
+
+const BAR_COUNT: usize = 22;
+// Straight pieces the Catmull-Rom surface between two bars is made of
+const CURVE_SEGMENTS: usize = 8;
+// Nudge used to look at both sides of a step in the blocky surface
+const EDGE_EPSILON: f32 = 0.01;
+
+/// How the surface runs between bar heights. Collision always follows the
+/// same surface that is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerrainStyle {
+    /// Flat-topped columns, like the Processing version
+    #[default]
+    Blocky,
+    /// Straight slopes from one bar centre to the next
+    Linear,
+    /// A smooth Catmull-Rom curve through the bar centres
+    CatmullRom,
 }
 
-impl Bar {
-    pub fn new(x: f32, y: f32, speed: f32, width: f32) -> Self {
-        Bar {
-            pos: Vec2::new(x, y),
-            width,
-            height: 0.0,
-            speed,
-            ascent: 0.0,
+impl TerrainStyle {
+    pub const ALL: [TerrainStyle; 3] = [TerrainStyle::Blocky, TerrainStyle::Linear, TerrainStyle::CatmullRom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TerrainStyle::Blocky => "blocky",
+            TerrainStyle::Linear => "linear",
+            TerrainStyle::CatmullRom => "smooth",
         }
     }
 
-    pub fn update(&mut self, mouse_pos: Vec2, speed_x: f32, ascent: f32, player: &mut Player) {
-        self.speed = speed_x;
-        self.ascent = ascent;
-        self.pos.x -= self.speed;
-        self.pos.y += self.ascent;
+    pub fn from_name(name: &str) -> Option<TerrainStyle> {
+        Self::ALL.into_iter().find(|style| style.name() == name)
+    }
 
-        // Adjust height based on mouse position
-        let half_width = self.width / 2.0;
-        if self.pos.x > mouse_pos.x - half_width && self.pos.x < mouse_pos.x + half_width {
-            self.pos.y = mouse_pos.y;
+    pub fn next(self) -> TerrainStyle {
+        match self {
+            TerrainStyle::Blocky => TerrainStyle::Linear,
+            TerrainStyle::Linear => TerrainStyle::CatmullRom,
+            TerrainStyle::CatmullRom => TerrainStyle::Blocky,
         }
+    }
 
-        // Update player terrain tracking
-        let check_zone = WINDOW_WIDTH / 6.0;
-        
-        if self.pos.x < check_zone && self.pos.x > check_zone - WINDOW_WIDTH/20.0 {
-            player.set_prev_bar_height(self.pos.y);
+    /// The surface height `t` bars to the right of the leftmost bar, where
+    /// `bar` gives the height of a bar by its position from the left.
+    fn height(self, t: f32, bar: impl Fn(usize) -> f32) -> f32 {
+        let (i, frac) = segment(t);
+        match self {
+            TerrainStyle::Blocky => bar(if frac < 0.5 { i } else { i + 1 }),
+            TerrainStyle::Linear => bar(i) + (bar(i + 1) - bar(i)) * frac,
+            TerrainStyle::CatmullRom => {
+                let (start, end, piece_frac) = curve_piece(i, frac, bar);
+                start + (end - start) * piece_frac
+            },
         }
-        if self.pos.x > check_zone && self.pos.x < check_zone + WINDOW_WIDTH/20.0 {
-            player.set_bar_height(self.pos.y);
+    }
+
+    /// The change in height per bar at `t`.
+    fn gradient(self, t: f32, bar: impl Fn(usize) -> f32) -> f32 {
+        let (i, frac) = segment(t);
+        match self {
+            TerrainStyle::Blocky => 0.0,
+            TerrainStyle::Linear => bar(i + 1) - bar(i),
+            TerrainStyle::CatmullRom => {
+                let (start, end, _) = curve_piece(i, frac, bar);
+                (end - start) * CURVE_SEGMENTS as f32
+            },
         }
     }
+}
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, window_height: f32) -> GameResult {
-        let bar_rect = Rect::new(
-            self.pos.x - self.width/2.0,
-            self.pos.y,
-            self.width,
-            window_height - self.pos.y
-        );
+/// What the sculpting brush does to the ground under it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrushMode {
+    /// Pushes the ground up
+    Raise,
+    /// Digs the ground down
+    Lower,
+    /// Levels the ground at the brush's height, like the Processing version
+    #[default]
+    Flatten,
+    /// Evens out each bar with its neighbours
+    Smooth,
+}
 
-        let mesh = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            bar_rect,
-            Color::from_rgb(92, 226, 0), // #5CE200
-        )?;
-        canvas.draw(&mesh, DrawParam::default());
-        Ok(())
+impl BrushMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            BrushMode::Raise => "raise",
+            BrushMode::Lower => "lower",
+            BrushMode::Flatten => "flatten",
+            BrushMode::Smooth => "smooth",
+        }
+    }
+
+    pub fn next(self) -> BrushMode {
+        match self {
+            BrushMode::Raise => BrushMode::Lower,
+            BrushMode::Lower => BrushMode::Flatten,
+            BrushMode::Flatten => BrushMode::Smooth,
+            BrushMode::Smooth => BrushMode::Raise,
+        }
+    }
+
+    pub fn previous(self) -> BrushMode {
+        match self {
+            BrushMode::Raise => BrushMode::Smooth,
+            BrushMode::Lower => BrushMode::Raise,
+            BrushMode::Flatten => BrushMode::Lower,
+            BrushMode::Smooth => BrushMode::Flatten,
+        }
+    }
+}
+
+/// How the brush's strength fades from its centre to its edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Falloff {
+    /// Full strength right out to the edge
+    Constant,
+    /// Fades evenly to nothing at the edge
+    Linear,
+    /// Eases in and out, so the edge blends into the untouched ground
+    #[default]
+    Smooth,
+}
+
+impl Falloff {
+    /// The brush strength, from 0 to 1, at `distance` as a fraction of the radius.
+    fn weight(self, distance: f32) -> f32 {
+        if distance >= 1.0 {
+            return 0.0;
+        }
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - distance,
+            Falloff::Smooth => 1.0 - distance * distance * (3.0 - 2.0 * distance),
+        }
     }
 }
 
+/// Where the brush is and what it does for one step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+    /// In world coordinates
+    pub pos: Vec2,
+    pub mode: BrushMode,
+    /// World y of the top and bottom of the view. Raising and lowering stop
+    /// there, as flattening does with the mouse on screen.
+    pub top: f32,
+    pub bottom: f32,
+}
+
+/// The shape and strength of the sculpting brush.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brush {
+    /// Bars whose centres are within this many pixels of the mouse are affected
+    pub radius: f32,
+    /// The most a bar moves per second, at the brush's centre
+    pub rate: f32,
+    pub falloff: Falloff,
+}
+
+/// Splits a position in bars into the bar at or left of it and how far it
+/// is towards the next one. Positions past either end are clamped to the outer bars.
+fn segment(t: f32) -> (usize, f32) {
+    let t = t.clamp(0.0, (BAR_COUNT - 1) as f32);
+    let i = (t as usize).min(BAR_COUNT - 2);
+    (i, t - i as f32)
+}
+
+/// The straight piece of the Catmull-Rom curve from bar `i` to `i + 1` that
+/// `frac` falls in: the heights at its ends and how far along it `frac` is.
+/// Drawing uses the same pieces, so the ground is exactly what's on screen.
+fn curve_piece(i: usize, frac: f32, bar: impl Fn(usize) -> f32) -> (f32, f32, f32) {
+    // Repeat the outer bars at either end
+    let points = [bar(i.saturating_sub(1)), bar(i), bar(i + 1), bar((i + 2).min(BAR_COUNT - 1))];
+    let scaled = frac * CURVE_SEGMENTS as f32;
+    let piece = scaled.floor().min((CURVE_SEGMENTS - 1) as f32);
+    (
+        catmull_rom(points, piece / CURVE_SEGMENTS as f32),
+        catmull_rom(points, (piece + 1.0) / CURVE_SEGMENTS as f32),
+        scaled - piece,
+    )
+}
+
+fn catmull_rom([p0, p1, p2, p3]: [f32; 4], t: f32) -> f32 {
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t)
+}
+
+/// The ground as a heightfield: one height per bar, evenly spaced and
+/// ordered left to right. Bars scroll left; when one leaves the screen its
+/// slot is reused for a new bar on the right, so the heights live in a ring
+/// buffer starting at `first`. Every bar's x follows from one shared scroll
+/// offset, so the bars can't drift out of step and never need realigning.
+///
+/// Between bar centres the surface follows `style`, so every `x` has a height.
 pub struct Terrain {
-    bars: Vec<Bar>,
+    heights: Vec<f32>,
+    prev_heights: Vec<f32>,
+    first: usize,
+    /// x of the leftmost bar's centre
+    scroll: f32,
+    prev_scroll: f32,
     bar_width: f32,
+    style: TerrainStyle,
 }
 
 impl Terrain {
-    pub fn new(window_width: f32, initial_height: f32, speed: f32) -> Self {
-        let bar_width = window_width / 20.0;
-        let mut bars = Vec::new();
-        
-        // Create initial set of bars
-        for i in 0..22 {
-            bars.push(Bar::new(
-                i as f32 * bar_width,
-                initial_height,
-                speed,
-                bar_width
-            ));
-        }
-
+    pub fn new(playfield_width: f32, initial_height: f32, style: TerrainStyle) -> Self {
         Terrain {
-            bars,
-            bar_width,
+            heights: vec![initial_height; BAR_COUNT],
+            prev_heights: vec![initial_height; BAR_COUNT],
+            first: 0,
+            scroll: 0.0,
+            prev_scroll: 0.0,
+            bar_width: playfield_width / 20.0,
+            style,
         }
     }
 
-    pub fn update(&mut self, mouse_pos: Vec2, speed_x: f32, ascent: f32, player: &mut Player) {
-        for bar in &mut self.bars {
-            bar.update(mouse_pos, speed_x, ascent, player);
-            
-            // Wrap bars around when they go off screen
-            if bar.pos.x < -2.0 * self.bar_width {
-                bar.pos.x = WINDOW_WIDTH;
-                bar.pos.y = mouse_pos.y;
+    pub fn set_style(&mut self, style: TerrainStyle) {
+        self.style = style;
+    }
+
+    /// Index into the ring buffer of the `i`th bar from the left.
+    fn slot(&self, i: usize) -> usize {
+        (self.first + i) % BAR_COUNT
+    }
+
+    fn bar_x(&self, i: usize) -> f32 {
+        self.scroll + i as f32 * self.bar_width
+    }
+
+    /// Scrolls the ground by `speed_x` and works it with `brush` along
+    /// `stroke` for a step of `dt` seconds. Bars scrolling in on the right
+    /// carry on at the height of the bar before them.
+    ///
+    /// Bars move by at most `budget` pixels in total. Returns how many pixels
+    /// of the budget were used.
+    pub fn update(&mut self, stroke: &Stroke, brush: &Brush, speed_x: f32, dt: f32, budget: f32) -> f32 {
+        self.prev_heights.copy_from_slice(&self.heights);
+        self.prev_scroll = self.scroll;
+        self.scroll -= speed_x;
+
+        // Work the bars under the brush, looking at the heights from before
+        // this step so the order they're visited in doesn't matter
+        let mut used = 0.0;
+        for i in 0..BAR_COUNT {
+            let strength = brush.falloff.weight((self.bar_x(i) - stroke.pos.x).abs() / brush.radius);
+            if strength <= 0.0 {
+                continue;
             }
+            let slot = self.slot(i);
+            let height = self.prev_heights[slot];
+            let target = match stroke.mode {
+                BrushMode::Raise => stroke.top,
+                BrushMode::Lower => stroke.bottom,
+                BrushMode::Flatten => stroke.pos.y,
+                BrushMode::Smooth => {
+                    let left = self.prev_heights[self.slot(i.saturating_sub(1))];
+                    let right = self.prev_heights[self.slot((i + 1).min(BAR_COUNT - 1))];
+                    (left + right) / 2.0
+                },
+            };
+            self.heights[slot] = reshape(height, target, brush.rate * dt * strength, budget, &mut used);
+        }
+
+        // Recycle bars that went off the left edge as new bars on the right
+        while self.scroll < -self.bar_width {
+            let last = self.heights[self.slot(BAR_COUNT - 1)];
+            self.heights[self.first] = last;
+            self.prev_heights[self.first] = last;
+            self.first = (self.first + 1) % BAR_COUNT;
+            self.scroll += self.bar_width;
+            self.prev_scroll += self.bar_width;
         }
+        used
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, window_height: f32) -> GameResult {
-        for bar in &self.bars {
-            bar.draw(ctx, canvas, window_height)?;
+    /// Draws the ground filled down to at least `bottom`, the lowest visible y.
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut graphics::Canvas, bottom: f32, alpha: f32) -> GameResult {
+        let color = Color::from_rgb(92, 226, 0); // #5CE200
+        let scroll = self.prev_scroll + (self.scroll - self.prev_scroll) * alpha;
+        let heights: Vec<f32> = (0..BAR_COUNT)
+            .map(|i| {
+                let slot = self.slot(i);
+                self.prev_heights[slot] + (self.heights[slot] - self.prev_heights[slot]) * alpha
+            })
+            .collect();
+        // Ground that has dropped out of view still gets a sliver drawn
+        let bottom = heights.iter().copied().fold(bottom, f32::max);
+
+        if self.style == TerrainStyle::Blocky {
+            for (i, &y) in heights.iter().enumerate() {
+                let x = scroll + i as f32 * self.bar_width;
+                let bar_rect = Rect::new(
+                    x - self.bar_width/2.0,
+                    y,
+                    self.bar_width,
+                    bottom - y
+                );
+
+                let mesh = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    bar_rect,
+                    color,
+                )?;
+                canvas.draw(&mesh, DrawParam::default());
+            }
+            return Ok(());
         }
+
+        // Smooth styles are one polygon: the surface from the first bar centre
+        // to the last, closed along the bottom of the view
+        let steps = (BAR_COUNT - 1) * CURVE_SEGMENTS;
+        let mut points: Vec<Vec2> = (0..=steps)
+            .map(|k| {
+                let t = k as f32 / CURVE_SEGMENTS as f32;
+                Vec2::new(scroll + t * self.bar_width, self.style.height(t, |i| heights[i]))
+            })
+            .collect();
+        points.push(Vec2::new(scroll + (BAR_COUNT - 1) as f32 * self.bar_width, bottom));
+        points.push(Vec2::new(scroll, bottom));
+
+        let mesh = graphics::Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &points, color)?;
+        canvas.draw(&mesh, DrawParam::default());
         Ok(())
     }
 
-    pub fn get_height_at(&self, x: f32) -> Option<f32> {
-        for bar in &self.bars {
-            if x > bar.pos.x - bar.width/2.0 && x < bar.pos.x + bar.width/2.0 {
-                return Some(bar.pos.y);
+    /// `x` in bars from the leftmost bar's centre.
+    fn bars_from_left(&self, x: f32) -> f32 {
+        (x - self.scroll) / self.bar_width
+    }
+
+    pub fn height_at(&self, x: f32) -> f32 {
+        self.style.height(self.bars_from_left(x), |i| self.heights[self.slot(i)])
+    }
+
+    /// The rate of change of the height at `x`. Positive slopes go downhill
+    /// to the right, since y grows downwards.
+    pub fn slope_at(&self, x: f32) -> f32 {
+        self.style.gradient(self.bars_from_left(x), |i| self.heights[self.slot(i)]) / self.bar_width
+    }
+
+    /// The unit vector pointing up and away from the surface at `x`.
+    pub fn normal_at(&self, x: f32) -> Vec2 {
+        Vec2::new(self.slope_at(x), -1.0).normalize()
+    }
+
+    /// Follows a point from `from` to `to` and returns the highest ground it
+    /// passed below, or ends within `buffer` above.
+    ///
+    /// The surface is checked at the end point and wherever it bends: bar
+    /// centres, the steps between blocky bars, or the pieces a curve is split
+    /// into. In between both the path and the surface are straight, so nothing
+    /// can be missed.
+    pub fn ground_along(&self, from: Vec2, to: Vec2, buffer: f32) -> Option<f32> {
+        let (step, offset) = match self.style {
+            TerrainStyle::Blocky => (1.0, 0.5),
+            TerrainStyle::Linear => (1.0, 0.0),
+            TerrainStyle::CatmullRom => (1.0 / CURVE_SEGMENTS as f32, 0.0),
+        };
+        let last = (BAR_COUNT - 1) as f32;
+        let left = self.bars_from_left(from.x.min(to.x)).max(-1.0);
+        let right = self.bars_from_left(from.x.max(to.x)).min(last + 1.0);
+        let first_k = ((left - offset) / step).ceil() as i32;
+        let last_k = ((right - offset) / step).floor() as i32;
+
+        let path_y = |x: f32| {
+            if to.x == from.x {
+                to.y
+            } else {
+                from.y + (to.y - from.y) * (x - from.x) / (to.x - from.x)
             }
+        };
+
+        let mut sample_xs = Vec::new();
+        for k in first_k..=last_k {
+            let x = self.scroll + (offset + k as f32 * step) * self.bar_width;
+            if self.style == TerrainStyle::Blocky {
+                // Check the bars on both sides of the step
+                sample_xs.push((x, x - EDGE_EPSILON));
+                sample_xs.push((x, x + EDGE_EPSILON));
+            } else {
+                sample_xs.push((x, x));
+            }
+        }
+
+        sample_xs.into_iter()
+            .filter_map(|(x, surface_x)| {
+                let height = self.height_at(surface_x);
+                (path_y(x) > height).then_some(height)
+            })
+            .chain(Some(self.height_at(to.x)).filter(|&height| to.y > height - buffer))
+            .reduce(f32::min)
+    }
+
+    /// How deep a circle overlaps the ground, pushing it out through the
+    /// nearest part of the surface: a slope, the top of a bar, or the
+    /// upright face of a blocky step. The normal points from the ground
+    /// towards the circle. A circle whose centre is inside the ground is
+    /// pushed back up to the surface.
+    pub fn circle_contact(&self, center: Vec2, radius: f32) -> Option<Contact> {
+        let closest = self.surface_between(center.x - radius, center.x + radius)
+            .windows(2)
+            .map(|piece| closest_on_segment(center, piece[0], piece[1]))
+            .min_by(|a, b| a.distance_squared(center).total_cmp(&b.distance_squared(center)))?;
+        let offset = center - closest;
+        let distance = offset.length();
+
+        if center.y > self.height_at(center.x) {
+            return Some(Contact {
+                normal: (-offset).try_normalize().unwrap_or(Vec2::NEG_Y),
+                depth: distance + radius,
+            });
+        }
+        if distance >= radius {
+            return None;
         }
-        None
+        Some(Contact {
+            normal: offset.try_normalize().unwrap_or(Vec2::NEG_Y),
+            depth: radius - distance,
+        })
     }
 
-    pub fn realign(&mut self) {
-        if let Some(first_bar) = self.bars.first() {
-            let base_x = first_bar.pos.x;
-            for (i, bar) in self.bars.iter_mut().enumerate() {
-                if bar.pos.x > base_x {
-                    bar.pos.x = base_x + (i as f32 * self.bar_width);
-                } else {
-                    bar.pos.x = base_x - ((22 - i) as f32 * self.bar_width);
+    /// The surface from `left` to at least `right` as a line through its
+    /// corners, left to right. Past the outer bars it carries on flat, like
+    /// `height_at`.
+    fn surface_between(&self, left: f32, right: f32) -> Vec<Vec2> {
+        let last = (BAR_COUNT - 1) as i32;
+        let bar = |i: i32| self.heights[self.slot(i.clamp(0, last) as usize)];
+        let x = |t: f32| self.scroll + t * self.bar_width;
+        let first = self.bars_from_left(left).floor() as i32 - 1;
+        let end = self.bars_from_left(right).ceil() as i32 + 1;
+
+        let mut points = Vec::new();
+        match self.style {
+            TerrainStyle::Blocky => {
+                // Across each bar's flat top, then down or up its step
+                for i in first..=end {
+                    points.push(Vec2::new(x(i as f32 - 0.5), bar(i)));
+                    points.push(Vec2::new(x(i as f32 + 0.5), bar(i)));
                 }
+            },
+            TerrainStyle::Linear | TerrainStyle::CatmullRom => {
+                let pieces = if self.style == TerrainStyle::Linear { 1 } else { CURVE_SEGMENTS as i32 };
+                for k in first * pieces..=end * pieces {
+                    let t = k as f32 / pieces as f32;
+                    points.push(Vec2::new(x(t), self.height_at(x(t))));
+                }
+            },
+        }
+        points
+    }
+}
+
+/// The point on the segment from `a` to `b` nearest to `point`.
+fn closest_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let along = b - a;
+    let length_squared = along.length_squared();
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = ((point - a).dot(along) / length_squared).clamp(0.0, 1.0);
+    a + along * t
+}
+
+/// Moves `height` towards `target` by no more than `step`, nor by more than
+/// is left of `budget` after `used`, adding the distance moved to `used`.
+fn reshape(height: f32, target: f32, step: f32, budget: f32, used: &mut f32) -> f32 {
+    let limit = step.min((budget - *used).max(0.0));
+    let change = (target - height).clamp(-limit, limit);
+    *used += change.abs();
+    height + change
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUND: f32 = 500.0;
+
+    /// Flat ground with the bars in `raised` lifted to `height`.
+    fn terrain_with(style: TerrainStyle, raised: std::ops::RangeInclusive<usize>, height: f32) -> Terrain {
+        let mut terrain = Terrain::new(640.0, GROUND, style);
+        for i in raised {
+            terrain.heights[i] = height;
+        }
+        terrain
+    }
+
+    #[test]
+    fn a_long_jump_into_a_ridge_finds_it() {
+        for style in TerrainStyle::ALL {
+            let terrain = terrain_with(style, 9..=11, 300.0);
+            // Twelve bars in one step, ending in the air past the ridge
+            let from = Vec2::new(terrain.bar_x(4), 450.0);
+            let to = Vec2::new(terrain.bar_x(16), 450.0);
+            let ground = terrain.ground_along(from, to, 0.0);
+            assert!(ground.is_some_and(|y| y <= 300.5 && y > 250.0), "{:?}: {:?}", style, ground);
+        }
+    }
+
+    #[test]
+    fn a_long_jump_into_a_one_bar_spike_finds_it() {
+        for style in TerrainStyle::ALL {
+            let terrain = terrain_with(style, 10..=10, 200.0);
+            let from = Vec2::new(terrain.bar_x(3), 450.0);
+            let to = Vec2::new(terrain.bar_x(17), 470.0);
+            let ground = terrain.ground_along(from, to, 0.0);
+            // The path passes under the tip, wherever the surface puts it
+            assert!(ground.is_some_and(|y| y <= 450.0), "{:?}: {:?}", style, ground);
+        }
+    }
+
+    #[test]
+    fn a_long_jump_over_a_ridge_finds_nothing() {
+        for style in TerrainStyle::ALL {
+            let terrain = terrain_with(style, 9..=11, 300.0);
+            let from = Vec2::new(terrain.bar_x(4), 200.0);
+            let to = Vec2::new(terrain.bar_x(16), 220.0);
+            assert_eq!(terrain.ground_along(from, to, 0.0), None, "{:?}", style);
+        }
+    }
+
+    #[test]
+    fn a_long_jump_ending_below_the_ground_lands_on_it() {
+        for style in TerrainStyle::ALL {
+            let terrain = Terrain::new(640.0, GROUND, style);
+            let from = Vec2::new(terrain.bar_x(4), 300.0);
+            let to = Vec2::new(terrain.bar_x(16), GROUND + 20.0);
+            assert_eq!(terrain.ground_along(from, to, 0.0), Some(GROUND), "{:?}", style);
+            // Ending just above it counts too, within the buffer
+            let hovering = Vec2::new(to.x, GROUND - 2.0);
+            assert_eq!(terrain.ground_along(from, hovering, 5.0), Some(GROUND), "{:?}", style);
+        }
+    }
+
+    #[test]
+    fn raising_and_lowering_stop_at_the_edges_of_the_view() {
+        let brush = Brush { radius: 100.0, rate: 1000.0, falloff: Falloff::Constant };
+        for (mode, limit) in [(BrushMode::Raise, 100.0), (BrushMode::Lower, 740.0)] {
+            let mut terrain = Terrain::new(640.0, GROUND, TerrainStyle::Blocky);
+            let stroke = Stroke { pos: Vec2::new(320.0, 400.0), mode, top: 100.0, bottom: 740.0 };
+            for _ in 0..600 {
+                terrain.update(&stroke, &brush, 0.0, 1.0 / 60.0, f32::INFINITY);
             }
+            assert_eq!(terrain.height_at(320.0), limit, "{:?}", mode);
         }
     }
 }
@@ -0,0 +1,44 @@
+use ggez::graphics::{self, Canvas, Color, DrawParam};
+use ggez::glam::Vec2;
+
+const DURATION: f32 = 4.0;
+const FADE_TIME: f32 = 1.0;
+const LINE_HEIGHT: f32 = 20.0;
+
+/// A short notice drawn over whatever screen is showing, fading out after a few seconds.
+pub struct Toast {
+    lines: Vec<String>,
+    color: Color,
+    remaining: f32,
+}
+
+impl Toast {
+    pub fn new(lines: Vec<String>, color: Color) -> Self {
+        Toast {
+            lines,
+            color,
+            remaining: DURATION,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.remaining -= dt;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, bottom_left: Vec2) {
+        let mut color = self.color;
+        color.a = (self.remaining / FADE_TIME).clamp(0.0, 1.0);
+
+        let top = bottom_left.y - self.lines.len() as f32 * LINE_HEIGHT;
+        for (i, line) in self.lines.iter().enumerate() {
+            let text = graphics::Text::new(line.as_str());
+            canvas.draw(&text, DrawParam::default()
+                .dest(Vec2::new(bottom_left.x, top + i as f32 * LINE_HEIGHT))
+                .color(color));
+        }
+    }
+}
@@ -0,0 +1,664 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::terrain::{Brush, Falloff};
+use crate::{SCALE_X, SCALE_Y, PLAYFIELD_WIDTH};
+
+/// Physics and difficulty constants, loaded from `resources/tuning.toml`.
+/// Any value missing from the file keeps its default. Distances and speeds
+/// are in playfield pixels; speeds are per simulation tick unless noted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    pub physics: PhysicsTuning,
+    pub pickups: PickupTuning,
+    pub boulders: BoulderTuning,
+    pub scoring: ScoringTuning,
+    pub powerups: PowerUpTuning,
+    pub fuel: FuelTuning,
+    pub damage: DamageTuning,
+    pub sculpting: SculptingTuning,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhysicsTuning {
+    /// Added to the truck's vertical velocity (px/s) every tick
+    pub gravity: f32,
+    /// Scroll speed at the start of a run
+    pub start_speed: f32,
+    pub max_speed: f32,
+    /// Change in scroll speed from each press of the A/S keys
+    pub manual_speed_step: f32,
+    /// Upward velocity of a jump, in px/s
+    pub jump_speed: f32,
+    /// Forward acceleration from the Right key while the wheels grip, in px/s²
+    pub throttle_accel: f32,
+    /// Deceleration from the Left key while the wheels grip, in px/s²
+    pub brake_decel: f32,
+    /// Fraction of the rolling speed lost per second
+    pub rolling_drag: f32,
+    /// Upward acceleration per pixel of compression of each wheel's spring, in px/s²
+    pub suspension_stiffness: f32,
+    /// Acceleration opposing each spring's movement per px/s it moves
+    pub suspension_damping: f32,
+    /// How far each wheel can move up into the body, in px
+    pub suspension_travel: f32,
+    /// Fraction of the chassis' spin lost per second
+    pub angular_damping: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PickupTuning {
+    pub coin_value: i32,
+    pub gem_value: i32,
+    /// Scroll speed gained by collecting any pickup
+    pub speed_bonus: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoulderTuning {
+    /// Smallest diameter of a boulder
+    pub min_size: f32,
+    /// Largest diameter of a boulder
+    pub max_size: f32,
+    /// Share of a boulder's speed into the ground or another boulder kept as it bounces off
+    pub restitution: f32,
+    /// Fraction of a boulder's rolling speed lost per second
+    pub rolling_drag: f32,
+    /// Fastest a new boulder starts rolling towards the truck, in px/s
+    pub spawn_roll_speed: f32,
+    /// Upward velocity, in px/s, of a truck landing on top of a boulder
+    pub bounce_speed: f32,
+    /// How close, in px, the truck must pass a boulder to score a near miss
+    pub near_miss_margin: f32,
+    pub near_miss_bonus: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringTuning {
+    /// Points for each metre the truck drives higher than it has been before
+    pub climb_points: i32,
+    /// Playfield pixels in a metre of altitude or distance
+    pub pixels_per_metre: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerUpTuning {
+    /// Chance that a coin comes back as a power-up instead
+    pub spawn_chance: f32,
+    /// How often each power-up spawns, relative to the others
+    pub shield_weight: f32,
+    pub magnet_weight: f32,
+    pub slow_motion_weight: f32,
+    pub super_jump_weight: f32,
+    /// How long each power-up lasts, in seconds
+    pub shield_duration: f32,
+    pub magnet_duration: f32,
+    pub slow_motion_duration: f32,
+    pub super_jump_duration: f32,
+    /// The most time, in seconds, a power-up can build up by collecting it again
+    pub max_stacked_duration: f32,
+    /// Pickups closer than this to the truck are pulled in by the magnet
+    pub magnet_radius: f32,
+    /// How far the magnet pulls a pickup each tick
+    pub magnet_pull: f32,
+    /// Multiplies the scroll speed while slow motion lasts
+    pub slow_motion_scale: f32,
+    /// Multiplies the jump speed while super jump lasts
+    pub super_jump_boost: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuelTuning {
+    /// A full tank
+    pub capacity: f32,
+    /// Fuel burnt per pixel driven
+    pub burn_per_pixel: f32,
+    /// Extra fuel burnt per pixel climbed with the wheels on the ground
+    pub climb_burn_per_pixel: f32,
+    /// Fraction of the speed lost per second once the tank is empty
+    pub empty_drag: f32,
+    /// Below this speed, in px/s, a truck with an empty tank has stopped
+    pub stall_speed: f32,
+    /// Fuel in a fuel can
+    pub can_amount: f32,
+    /// Chance that a gem comes back as a fuel can instead
+    pub can_chance: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DamageTuning {
+    /// The truck's health when undamaged
+    pub max_health: f32,
+    /// Health lost per px/s of speed the truck hits a boulder at
+    pub damage_per_speed: f32,
+    /// The least a boulder hit does, however slow
+    pub min_damage: f32,
+    /// Damage multiplier for boulders landing on the roof
+    pub roof_multiplier: f32,
+    /// Damage multiplier for boulders hitting the underside
+    pub underside_multiplier: f32,
+    /// Seconds after a hit during which nothing else hurts the truck
+    pub invulnerable_time: f32,
+    /// Landing faster than this, in px/s, damages the truck
+    pub safe_landing_speed: f32,
+    /// Health lost per px/s of landing speed over the safe speed
+    pub landing_damage_per_speed: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SculptingTuning {
+    /// A full ink budget
+    pub capacity: f32,
+    /// Ink spent per pixel a bar is raised or lowered
+    pub cost_per_pixel: f32,
+    /// Ink regained per second
+    pub regen_per_second: f32,
+    /// Ink in an ink pot
+    pub pot_amount: f32,
+    /// Chance that a gem comes back as an ink pot instead
+    pub pot_chance: f32,
+    /// Reach of the brush either side of the mouse
+    pub brush_radius: f32,
+    /// The most the ground moves per second under the brush's centre
+    pub brush_rate: f32,
+    /// How the brush fades towards its edge
+    pub falloff: Falloff,
+}
+
+impl Default for PhysicsTuning {
+    fn default() -> Self {
+        PhysicsTuning {
+            gravity: 15.0 * SCALE_Y,
+            start_speed: 4.0 * SCALE_X,
+            max_speed: 20.0,
+            manual_speed_step: 0.2,
+            jump_speed: 500.0,
+            throttle_accel: 300.0,
+            brake_decel: 600.0,
+            rolling_drag: 0.05,
+            suspension_stiffness: 150.0,
+            suspension_damping: 20.0,
+            suspension_travel: 8.0,
+            angular_damping: 1.0,
+        }
+    }
+}
+
+impl Default for PickupTuning {
+    fn default() -> Self {
+        PickupTuning {
+            coin_value: 25,
+            gem_value: 50,
+            speed_bonus: 5.0 * SCALE_X,
+        }
+    }
+}
+
+impl Default for BoulderTuning {
+    fn default() -> Self {
+        BoulderTuning {
+            min_size: PLAYFIELD_WIDTH / 20.0,
+            max_size: 6.0 * PLAYFIELD_WIDTH / 20.0,
+            restitution: 0.3,
+            rolling_drag: 0.2,
+            spawn_roll_speed: 120.0,
+            bounce_speed: 300.0,
+            near_miss_margin: 12.0,
+            near_miss_bonus: 10,
+        }
+    }
+}
+
+impl Default for PowerUpTuning {
+    fn default() -> Self {
+        PowerUpTuning {
+            spawn_chance: 0.25,
+            shield_weight: 1.0,
+            magnet_weight: 1.0,
+            slow_motion_weight: 1.0,
+            super_jump_weight: 1.0,
+            shield_duration: 10.0,
+            magnet_duration: 8.0,
+            slow_motion_duration: 5.0,
+            super_jump_duration: 8.0,
+            max_stacked_duration: 20.0,
+            magnet_radius: 200.0,
+            magnet_pull: 8.0,
+            slow_motion_scale: 0.5,
+            super_jump_boost: 1.5,
+        }
+    }
+}
+
+impl Default for FuelTuning {
+    fn default() -> Self {
+        FuelTuning {
+            capacity: 100.0,
+            burn_per_pixel: 0.008,
+            climb_burn_per_pixel: 0.05,
+            empty_drag: 0.6,
+            stall_speed: 20.0,
+            can_amount: 40.0,
+            can_chance: 0.2,
+        }
+    }
+}
+
+impl Default for DamageTuning {
+    fn default() -> Self {
+        DamageTuning {
+            max_health: 100.0,
+            damage_per_speed: 0.06,
+            min_damage: 5.0,
+            roof_multiplier: 1.5,
+            underside_multiplier: 0.5,
+            invulnerable_time: 1.5,
+            safe_landing_speed: 650.0,
+            landing_damage_per_speed: 0.15,
+        }
+    }
+}
+
+impl Default for SculptingTuning {
+    fn default() -> Self {
+        SculptingTuning {
+            capacity: 600.0,
+            cost_per_pixel: 1.0,
+            regen_per_second: 60.0,
+            pot_amount: 300.0,
+            pot_chance: 0.15,
+            brush_radius: 48.0,
+            brush_rate: 800.0,
+            falloff: Falloff::Smooth,
+        }
+    }
+}
+
+impl SculptingTuning {
+    pub fn brush(&self) -> Brush {
+        Brush {
+            radius: self.brush_radius,
+            rate: self.brush_rate,
+            falloff: self.falloff,
+        }
+    }
+}
+
+impl ScoringTuning {
+    /// Converts a distance in playfield pixels to whole metres, rounding down.
+    pub fn metres(&self, pixels: f32) -> i32 {
+        (pixels / self.pixels_per_metre).floor() as i32
+    }
+}
+
+impl Default for ScoringTuning {
+    fn default() -> Self {
+        ScoringTuning {
+            climb_points: 2,
+            pixels_per_metre: 10.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TuningError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid { field: &'static str, reason: &'static str },
+}
+
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TuningError::Io(e) => write!(f, "could not read tuning file: {}", e),
+            TuningError::Parse(e) => write!(f, "could not parse tuning file: {}", e),
+            TuningError::Invalid { field, reason } => write!(f, "invalid tuning value {}: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for TuningError {}
+
+impl Tuning {
+    pub fn from_toml(source: &str) -> Result<Tuning, TuningError> {
+        let tuning: Tuning = toml::from_str(source).map_err(TuningError::Parse)?;
+        tuning.validate()?;
+        Ok(tuning)
+    }
+
+    /// Lists every value that differs from `other`, as `"section.key: old -> new"`.
+    pub fn changes_from(&self, other: &Tuning) -> Vec<String> {
+        let (Ok(toml::Value::Table(new)), Ok(toml::Value::Table(old))) =
+            (toml::Value::try_from(self), toml::Value::try_from(other)) else {
+            return Vec::new();
+        };
+
+        let mut changes = Vec::new();
+        for (section, values) in &new {
+            let (Some(values), Some(old_values)) = (
+                values.as_table(),
+                old.get(section).and_then(toml::Value::as_table),
+            ) else {
+                continue;
+            };
+            for (key, value) in values {
+                if let Some(old_value) = old_values.get(key) {
+                    if old_value != value {
+                        changes.push(format!("{}.{}: {} -> {}", section, key, old_value, value));
+                    }
+                }
+            }
+        }
+        changes
+    }
+
+    pub fn validate(&self) -> Result<(), TuningError> {
+        let physics = &self.physics;
+        non_negative("physics.gravity", physics.gravity)?;
+        non_negative("physics.start_speed", physics.start_speed)?;
+        non_negative("physics.max_speed", physics.max_speed)?;
+        non_negative("physics.manual_speed_step", physics.manual_speed_step)?;
+        non_negative("physics.jump_speed", physics.jump_speed)?;
+        non_negative("physics.throttle_accel", physics.throttle_accel)?;
+        non_negative("physics.brake_decel", physics.brake_decel)?;
+        non_negative("physics.rolling_drag", physics.rolling_drag)?;
+        non_negative("physics.suspension_stiffness", physics.suspension_stiffness)?;
+        non_negative("physics.suspension_damping", physics.suspension_damping)?;
+        non_negative("physics.suspension_travel", physics.suspension_travel)?;
+        non_negative("physics.angular_damping", physics.angular_damping)?;
+        if physics.start_speed > physics.max_speed {
+            return Err(TuningError::Invalid {
+                field: "physics.start_speed",
+                reason: "must not exceed physics.max_speed",
+            });
+        }
+
+        non_negative("pickups.speed_bonus", self.pickups.speed_bonus)?;
+
+        non_negative("boulders.min_size", self.boulders.min_size)?;
+        non_negative("boulders.max_size", self.boulders.max_size)?;
+        non_negative("boulders.restitution", self.boulders.restitution)?;
+        non_negative("boulders.rolling_drag", self.boulders.rolling_drag)?;
+        non_negative("boulders.spawn_roll_speed", self.boulders.spawn_roll_speed)?;
+        non_negative("boulders.bounce_speed", self.boulders.bounce_speed)?;
+        non_negative("boulders.near_miss_margin", self.boulders.near_miss_margin)?;
+        if self.boulders.min_size == 0.0 {
+            return Err(TuningError::Invalid {
+                field: "boulders.min_size",
+                reason: "must be greater than zero",
+            });
+        }
+        if self.boulders.max_size <= self.boulders.min_size {
+            return Err(TuningError::Invalid {
+                field: "boulders.max_size",
+                reason: "must be greater than boulders.min_size",
+            });
+        }
+
+        if !(self.scoring.pixels_per_metre.is_finite() && self.scoring.pixels_per_metre > 0.0) {
+            return Err(TuningError::Invalid {
+                field: "scoring.pixels_per_metre",
+                reason: "must be greater than zero",
+            });
+        }
+
+        let powerups = &self.powerups;
+        non_negative("powerups.spawn_chance", powerups.spawn_chance)?;
+        non_negative("powerups.shield_weight", powerups.shield_weight)?;
+        non_negative("powerups.magnet_weight", powerups.magnet_weight)?;
+        non_negative("powerups.slow_motion_weight", powerups.slow_motion_weight)?;
+        non_negative("powerups.super_jump_weight", powerups.super_jump_weight)?;
+        non_negative("powerups.shield_duration", powerups.shield_duration)?;
+        non_negative("powerups.magnet_duration", powerups.magnet_duration)?;
+        non_negative("powerups.slow_motion_duration", powerups.slow_motion_duration)?;
+        non_negative("powerups.super_jump_duration", powerups.super_jump_duration)?;
+        non_negative("powerups.max_stacked_duration", powerups.max_stacked_duration)?;
+        non_negative("powerups.magnet_radius", powerups.magnet_radius)?;
+        non_negative("powerups.magnet_pull", powerups.magnet_pull)?;
+        non_negative("powerups.slow_motion_scale", powerups.slow_motion_scale)?;
+        non_negative("powerups.super_jump_boost", powerups.super_jump_boost)?;
+        if powerups.spawn_chance > 1.0 {
+            return Err(TuningError::Invalid {
+                field: "powerups.spawn_chance",
+                reason: "must not exceed 1.0",
+            });
+        }
+
+        let fuel = &self.fuel;
+        non_negative("fuel.capacity", fuel.capacity)?;
+        non_negative("fuel.burn_per_pixel", fuel.burn_per_pixel)?;
+        non_negative("fuel.climb_burn_per_pixel", fuel.climb_burn_per_pixel)?;
+        non_negative("fuel.empty_drag", fuel.empty_drag)?;
+        non_negative("fuel.stall_speed", fuel.stall_speed)?;
+        non_negative("fuel.can_amount", fuel.can_amount)?;
+        non_negative("fuel.can_chance", fuel.can_chance)?;
+        if fuel.capacity == 0.0 {
+            return Err(TuningError::Invalid {
+                field: "fuel.capacity",
+                reason: "must be greater than zero",
+            });
+        }
+        if fuel.can_chance > 1.0 {
+            return Err(TuningError::Invalid {
+                field: "fuel.can_chance",
+                reason: "must not exceed 1.0",
+            });
+        }
+
+        let damage = &self.damage;
+        non_negative("damage.max_health", damage.max_health)?;
+        non_negative("damage.damage_per_speed", damage.damage_per_speed)?;
+        non_negative("damage.min_damage", damage.min_damage)?;
+        non_negative("damage.roof_multiplier", damage.roof_multiplier)?;
+        non_negative("damage.underside_multiplier", damage.underside_multiplier)?;
+        non_negative("damage.invulnerable_time", damage.invulnerable_time)?;
+        non_negative("damage.safe_landing_speed", damage.safe_landing_speed)?;
+        non_negative("damage.landing_damage_per_speed", damage.landing_damage_per_speed)?;
+        if damage.max_health == 0.0 {
+            return Err(TuningError::Invalid {
+                field: "damage.max_health",
+                reason: "must be greater than zero",
+            });
+        }
+
+        let sculpting = &self.sculpting;
+        non_negative("sculpting.capacity", sculpting.capacity)?;
+        non_negative("sculpting.cost_per_pixel", sculpting.cost_per_pixel)?;
+        non_negative("sculpting.regen_per_second", sculpting.regen_per_second)?;
+        non_negative("sculpting.pot_amount", sculpting.pot_amount)?;
+        non_negative("sculpting.pot_chance", sculpting.pot_chance)?;
+        non_negative("sculpting.brush_radius", sculpting.brush_radius)?;
+        non_negative("sculpting.brush_rate", sculpting.brush_rate)?;
+        if sculpting.capacity == 0.0 {
+            return Err(TuningError::Invalid {
+                field: "sculpting.capacity",
+                reason: "must be greater than zero",
+            });
+        }
+        if sculpting.brush_radius == 0.0 {
+            return Err(TuningError::Invalid {
+                field: "sculpting.brush_radius",
+                reason: "must be greater than zero",
+            });
+        }
+        if self.fuel.can_chance + sculpting.pot_chance > 1.0 {
+            return Err(TuningError::Invalid {
+                field: "sculpting.pot_chance",
+                reason: "must not exceed 1.0 together with fuel.can_chance",
+            });
+        }
+        Ok(())
+    }
+}
+
+fn non_negative(field: &'static str, value: f32) -> Result<(), TuningError> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(TuningError::Invalid {
+            field,
+            reason: "must be a finite, non-negative number",
+        })
+    }
+}
+
+/// Watches the tuning file on disk so edits can be applied to a running game.
+pub struct TuningWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl TuningWatcher {
+    const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        TuningWatcher {
+            path,
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Reloads the file if it was modified since the last call. Returns
+    /// `None` when there is nothing new.
+    pub fn poll(&mut self) -> Option<Result<Tuning, TuningError>> {
+        if self.last_check.elapsed() < Self::CHECK_INTERVAL {
+            return None;
+        }
+        self.last_check = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(std::fs::read_to_string(&self.path)
+            .map_err(TuningError::Io)
+            .and_then(|source| Tuning::from_toml(&source)))
+    }
+}
+
+fn modified_time(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIPPED: &str = include_str!("../resources/tuning.toml");
+
+    /// The default tuning with one value changed by `edit`.
+    fn with(edit: impl FnOnce(&mut Tuning)) -> Tuning {
+        let mut tuning = Tuning::default();
+        edit(&mut tuning);
+        tuning
+    }
+
+    fn invalid_field(tuning: &Tuning) -> Option<&'static str> {
+        match tuning.validate() {
+            Err(TuningError::Invalid { field, .. }) => Some(field),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn the_shipped_file_matches_the_defaults() {
+        let shipped = Tuning::from_toml(SHIPPED).unwrap();
+        assert_eq!(shipped.changes_from(&Tuning::default()), Vec::<String>::new());
+        assert_eq!(shipped, Tuning::default());
+    }
+
+    #[test]
+    fn an_empty_file_is_all_defaults() {
+        assert_eq!(Tuning::from_toml("").unwrap(), Tuning::default());
+        let partial = Tuning::from_toml("[physics]\ngravity = 7.5\n").unwrap();
+        assert_eq!(partial, with(|t| t.physics.gravity = 7.5));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(matches!(Tuning::from_toml("[physics]\ngravityy = 1.0\n"), Err(TuningError::Parse(_))));
+        assert!(matches!(Tuning::from_toml("[nonsense]\n"), Err(TuningError::Parse(_))));
+    }
+
+    #[test]
+    fn negative_and_non_finite_values_are_rejected() {
+        for (field, tuning) in [
+            ("physics.gravity", with(|t| t.physics.gravity = -1.0)),
+            ("physics.manual_speed_step", with(|t| t.physics.manual_speed_step = f32::NAN)),
+            ("boulders.restitution", with(|t| t.boulders.restitution = -0.5)),
+            ("powerups.magnet_pull", with(|t| t.powerups.magnet_pull = f32::INFINITY)),
+            ("fuel.burn_per_pixel", with(|t| t.fuel.burn_per_pixel = -0.001)),
+            ("damage.min_damage", with(|t| t.damage.min_damage = -10.0)),
+            ("sculpting.brush_rate", with(|t| t.sculpting.brush_rate = -1.0)),
+        ] {
+            assert_eq!(invalid_field(&tuning), Some(field));
+        }
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        for (field, tuning) in [
+            ("physics.start_speed", with(|t| t.physics.start_speed = t.physics.max_speed + 1.0)),
+            ("boulders.min_size", with(|t| t.boulders.min_size = 0.0)),
+            ("boulders.max_size", with(|t| t.boulders.max_size = t.boulders.min_size)),
+            ("scoring.pixels_per_metre", with(|t| t.scoring.pixels_per_metre = 0.0)),
+            ("powerups.spawn_chance", with(|t| t.powerups.spawn_chance = 1.5)),
+            ("fuel.capacity", with(|t| t.fuel.capacity = 0.0)),
+            ("fuel.can_chance", with(|t| t.fuel.can_chance = 1.1)),
+            ("damage.max_health", with(|t| t.damage.max_health = 0.0)),
+            ("sculpting.capacity", with(|t| t.sculpting.capacity = 0.0)),
+            ("sculpting.brush_radius", with(|t| t.sculpting.brush_radius = 0.0)),
+            ("sculpting.pot_chance", with(|t| {
+                t.fuel.can_chance = 0.6;
+                t.sculpting.pot_chance = 0.5;
+            })),
+        ] {
+            assert_eq!(invalid_field(&tuning), Some(field));
+        }
+        assert!(Tuning::default().validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_values_in_a_file_are_reported() {
+        let error = Tuning::from_toml("[fuel]\ncapacity = -5.0\n").err().unwrap();
+        assert!(matches!(error, TuningError::Invalid { field: "fuel.capacity", .. }));
+        assert!(error.to_string().contains("fuel.capacity"));
+    }
+
+    #[test]
+    fn changes_are_listed_by_section_and_key() {
+        let old = Tuning::default();
+        assert!(old.changes_from(&old).is_empty());
+
+        let new = with(|t| {
+            t.physics.gravity = 30.0;
+            t.scoring.climb_points = 5;
+            t.sculpting.falloff = Falloff::Linear;
+        });
+        let mut changes = new.changes_from(&old);
+        changes.sort();
+        assert_eq!(changes, [
+            "physics.gravity: 20.0 -> 30.0",
+            "scoring.climb_points: 2 -> 5",
+            "sculpting.falloff: \"smooth\" -> \"linear\"",
+        ]);
+    }
+}
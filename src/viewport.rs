@@ -0,0 +1,106 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Canvas, Color, DrawParam, Image, Rect};
+use ggez::glam::Vec2;
+
+use crate::{PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT, ORIGINAL_WIDTH, ORIGINAL_HEIGHT};
+
+/// Maps the fixed-size logical playfield onto a window of any size. The
+/// playfield is scaled to fit while keeping its aspect ratio, centred, and
+/// the leftover space is letterboxed.
+///
+/// In pixel-perfect mode the scene is instead rendered at the original
+/// 480×480 resolution and enlarged by a whole-number factor.
+pub struct Viewport {
+    window_size: Vec2,
+    pixel_perfect: bool,
+    scale: f32,
+    offset: Vec2,
+}
+
+impl Viewport {
+    pub fn new(window_width: f32, window_height: f32, pixel_perfect: bool) -> Self {
+        let mut viewport = Viewport {
+            window_size: Vec2::ZERO,
+            pixel_perfect,
+            scale: 1.0,
+            offset: Vec2::ZERO,
+        };
+        viewport.resize(window_width, window_height);
+        viewport
+    }
+
+    pub fn resize(&mut self, window_width: f32, window_height: f32) {
+        self.window_size = Vec2::new(window_width.max(1.0), window_height.max(1.0));
+        self.update_scale();
+    }
+
+    pub fn pixel_perfect(&self) -> bool {
+        self.pixel_perfect
+    }
+
+    pub fn set_pixel_perfect(&mut self, pixel_perfect: bool) {
+        self.pixel_perfect = pixel_perfect;
+        self.update_scale();
+    }
+
+    fn update_scale(&mut self) {
+        self.scale = if self.pixel_perfect {
+            let factor = (self.window_size.x / ORIGINAL_WIDTH)
+                .min(self.window_size.y / ORIGINAL_HEIGHT)
+                .floor()
+                .max(1.0);
+            factor * ORIGINAL_WIDTH / PLAYFIELD_WIDTH
+        } else {
+            (self.window_size.x / PLAYFIELD_WIDTH).min(self.window_size.y / PLAYFIELD_HEIGHT)
+        };
+        self.offset = ((self.window_size - Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT) * self.scale) / 2.0).floor();
+    }
+
+    /// The playfield coordinates visible across the whole window, suitable
+    /// for `Canvas::set_screen_coordinates`.
+    pub fn screen_coordinates(&self) -> Rect {
+        Rect::new(
+            -self.offset.x / self.scale,
+            -self.offset.y / self.scale,
+            self.window_size.x / self.scale,
+            self.window_size.y / self.scale
+        )
+    }
+
+    /// Converts a position in window pixels, such as the mouse, to playfield coordinates.
+    pub fn to_playfield(&self, window_pos: Vec2) -> Vec2 {
+        (window_pos - self.offset) / self.scale
+    }
+
+    /// Covers everything outside the playfield so nothing drawn past its edges shows.
+    pub fn draw_letterbox(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let screen = self.screen_coordinates();
+        let bars = [
+            Rect::new(screen.x, screen.y, -screen.x, screen.h),
+            Rect::new(PLAYFIELD_WIDTH, screen.y, screen.right() - PLAYFIELD_WIDTH, screen.h),
+            Rect::new(screen.x, screen.y, screen.w, -screen.y),
+            Rect::new(screen.x, PLAYFIELD_HEIGHT, screen.w, screen.bottom() - PLAYFIELD_HEIGHT),
+        ];
+        for bar in bars {
+            if bar.w > 0.0 && bar.h > 0.0 {
+                let mesh = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    bar,
+                    Color::BLACK,
+                )?;
+                canvas.draw(&mesh, DrawParam::default());
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the scene rendered at the original resolution onto the window
+    /// frame. The frame canvas should use a nearest-neighbour sampler.
+    pub fn draw_pixel_perfect(&self, frame: &mut Canvas, low_res: &Image) {
+        let factor = self.scale * PLAYFIELD_WIDTH / ORIGINAL_WIDTH;
+        frame.draw(low_res, DrawParam::default()
+            .dest(self.offset)
+            .scale(Vec2::splat(factor)));
+    }
+}
@@ -0,0 +1,529 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{Canvas, Rect};
+use ggez::glam::Vec2;
+
+use crate::camera::Camera;
+use crate::collision::{self, Collider, Shape};
+use crate::terrain::{BrushMode, Stroke, Terrain, TerrainStyle};
+use crate::player::Player;
+use crate::background::Background;
+use crate::boulder::Boulder;
+use crate::pickup::{Pickup, PickupType};
+use crate::powerup::PowerUp;
+use crate::rng::GameRng;
+use crate::tuning::Tuning;
+use crate::{PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT, TICK_DT};
+
+/// Everything the simulation needs to know about the player's input for one step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameInput {
+    /// In screen coordinates; the world converts it with its camera
+    pub mouse_pos: Vec2,
+    pub jump: bool,
+    pub throttle: bool,
+    pub brake: bool,
+    /// One-shot presses of the A/S keys, nudging the speed up or down
+    pub speed_up: bool,
+    pub slow_down: bool,
+    /// What sculpting does to the ground under the mouse
+    pub brush: BrushMode,
+}
+
+/// How far a run has got, in playfield pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunStats {
+    /// Ground covered since the start
+    pub distance: f32,
+    /// Height of the ground under the truck above the ground it started on
+    pub altitude: f32,
+    /// The highest altitude the truck has driven at
+    pub best_altitude: f32,
+}
+
+// Height of the flat ground a run starts on
+const START_GROUND: f32 = PLAYFIELD_HEIGHT - PLAYFIELD_HEIGHT/6.0;
+
+/// The gameplay simulation. Owns every moving object and can be stepped
+/// without a ggez `Context`; drawing is a read-only pass over it.
+///
+/// Positions are in world coordinates, which `camera` maps to the screen.
+pub struct World {
+    pub terrain: Terrain,
+    pub background: Background,
+    pub player: Player,
+    pub boulders: Vec<Boulder>,
+    pub pickups: Vec<Pickup>,
+    pub rng: GameRng,
+    pub score: i32,
+    /// How far the world scrolls each step; follows the truck's speed
+    pub speed_x: f32,
+    pub tuning: Tuning,
+    pub camera: Camera,
+    pub stats: RunStats,
+    /// What's left of the budget for reshaping the ground
+    pub ink: f32,
+    pub game_over: bool,
+}
+
+impl World {
+    /// Builds a fresh run whose every random choice follows from `seed`.
+    pub fn new(seed: u64, terrain_style: TerrainStyle, tuning: &Tuning) -> Self {
+        let mut rng = GameRng::new(seed);
+
+        let mut boulders = Vec::new();
+        for _ in 0..2 {
+            let y = rng.gen_f32() * PLAYFIELD_HEIGHT * 0.75;
+            boulders.push(Boulder::new(
+                PLAYFIELD_WIDTH,
+                y,
+                &tuning.boulders,
+                &mut rng
+            ));
+        }
+
+        let mut pickups = Vec::new();
+        // Add coins
+        for i in 0..2 {
+            pickups.push(Pickup::new(
+                PLAYFIELD_WIDTH + i as f32 * 200.0,
+                rng.gen_f32() * PLAYFIELD_HEIGHT,
+                PickupType::Coin,
+                &tuning.pickups
+            ));
+        }
+        // Add gems
+        for i in 0..2 {
+            pickups.push(Pickup::new(
+                PLAYFIELD_WIDTH + i as f32 * 300.0,
+                rng.gen_f32() * PLAYFIELD_HEIGHT,
+                PickupType::Gem,
+                &tuning.pickups
+            ));
+        }
+
+        let background = Background::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT, &mut rng);
+
+        World {
+            terrain: Terrain::new(PLAYFIELD_WIDTH, START_GROUND, terrain_style),
+            background,
+            // Drop in from just above the ground, gently enough not to take damage
+            player: Player::new(
+                PLAYFIELD_WIDTH/4.0,
+                START_GROUND - 40.0,
+                tuning.physics.start_speed / TICK_DT,
+                tuning
+            ),
+            boulders,
+            pickups,
+            rng,
+            score: 0,
+            speed_x: tuning.physics.start_speed,
+            tuning: tuning.clone(),
+            camera: Camera::new(0.0),
+            stats: RunStats::default(),
+            ink: tuning.sculpting.capacity,
+            game_over: false,
+        }
+    }
+
+    /// Switches a run in progress over to new tuning values. Boulder sizes take
+    /// effect as boulders respawn.
+    pub fn apply_tuning(&mut self, tuning: &Tuning) {
+        self.player.apply_tuning(&tuning.physics);
+        for pickup in &mut self.pickups {
+            pickup.apply_tuning(&tuning.pickups);
+        }
+        self.tuning = tuning.clone();
+    }
+
+    /// Advances the simulation by one step of `dt` seconds.
+    pub fn step(&mut self, input: &FrameInput, dt: f32) {
+        if self.game_over {
+            return;
+        }
+
+        // Manual speed adjustments
+        let step = self.tuning.physics.manual_speed_step / dt;
+        let velocity = &mut self.player.velocity;
+        if input.speed_up {
+            velocity.x = (velocity.x + step).min(self.tuning.physics.max_speed / dt);
+        }
+        if input.slow_down {
+            velocity.x = (velocity.x - step).max(0.0);
+        }
+
+        self.speed_x = self.player.velocity.x * dt;
+        if self.player.effects.is_active(PowerUp::SlowMotion) {
+            self.speed_x *= self.tuning.powerups.slow_motion_scale;
+        }
+
+        // How far the ground scrolls under the truck this step
+        let terrain_motion = Vec2::new(-self.speed_x, 0.0);
+
+        // Update terrain, sculpting it where the mouse was on the last frame
+        // shown for as long as the ink lasts
+        let sculpting = &self.tuning.sculpting;
+        self.ink = (self.ink + sculpting.regen_per_second * dt).min(sculpting.capacity);
+        let budget = if sculpting.cost_per_pixel > 0.0 {
+            self.ink / sculpting.cost_per_pixel
+        } else {
+            f32::INFINITY
+        };
+        let stroke = Stroke {
+            pos: self.camera.to_world(input.mouse_pos),
+            mode: input.brush,
+            top: self.camera.top(),
+            bottom: self.camera.top() + PLAYFIELD_HEIGHT,
+        };
+        let sculpted = self.terrain.update(
+            &stroke,
+            &sculpting.brush(),
+            self.speed_x,
+            dt,
+            budget
+        );
+        self.ink = (self.ink - sculpted * sculpting.cost_per_pixel).max(0.0);
+
+        // Drive the truck over it
+        self.player.set_pedals(input.throttle, input.brake);
+        if input.jump {
+            let boost = if self.player.effects.is_active(PowerUp::SuperJump) {
+                self.tuning.powerups.super_jump_boost
+            } else {
+                1.0
+            };
+            self.player.jump(boost);
+        }
+        self.player.update(dt, &self.tuning, &self.terrain, terrain_motion);
+        self.camera.follow(self.player.pos.y);
+        self.track_progress();
+
+        if self.player.has_fallen(&self.terrain)
+            || self.player.has_flipped()
+            || self.player.has_stalled(self.tuning.fuel.stall_speed)
+            || self.player.is_wrecked()
+        {
+            self.game_over = true;
+            return;
+        }
+
+        // The mountains sink as the view climbs
+        self.background.update(self.speed_x, self.camera.ascent(), &mut self.rng);
+
+        for boulder in &mut self.boulders {
+            boulder.update(dt, self.speed_x, &self.tuning, &self.terrain);
+        }
+        for i in 1..self.boulders.len() {
+            let (left, right) = self.boulders.split_at_mut(i);
+            for other in left {
+                other.collide(&mut right[0], self.tuning.boulders.restitution);
+            }
+        }
+
+        let boulder_tuning = &self.tuning.boulders;
+        for boulder in &mut self.boulders {
+            // Check if boulder is off screen
+            if boulder.has_left_view(PLAYFIELD_WIDTH, self.camera.top(), PLAYFIELD_HEIGHT) {
+                boulder.reposition(PLAYFIELD_WIDTH, self.camera.top(), PLAYFIELD_HEIGHT, boulder_tuning, &mut self.rng);
+            }
+
+            // Check collision with player
+            if let Some(hit) = collision::sweep(&self.player, boulder) {
+                let contact = hit.contact;
+                // Landing on top of a boulder bounces the truck off it; any other hit is a crash
+                if contact.normal.y > 0.7 && self.player.velocity.y >= 0.0 {
+                    // Put the truck back where it first touched the boulder. The
+                    // truck always stays at the same x, so only its height changes
+                    let rewind = (1.0 - hit.time) * (boulder.motion() - self.player.motion())
+                        - contact.normal * contact.depth;
+                    self.player.pos.y += rewind.y;
+                    self.player.velocity.y = -boulder_tuning.bounce_speed;
+                    self.player.on_ground = false;
+                } else if self.player.is_invulnerable() {
+                    // Still recovering from the last hit, so the truck passes through
+                } else if self.player.effects.consume(PowerUp::Shield) {
+                    // The shield takes the hit and smashes the boulder
+                    boulder.reposition(PLAYFIELD_WIDTH, self.camera.top(), PLAYFIELD_HEIGHT, boulder_tuning, &mut self.rng);
+                } else {
+                    // How fast the truck and boulder were closing in on each other
+                    let impact_speed = (self.player.motion() - boulder.motion()).dot(contact.normal).max(0.0) / dt;
+                    let damage = self.player.impact_damage(impact_speed, contact.normal, &self.tuning.damage);
+                    self.player.take_damage(damage, &self.tuning.damage);
+                    if self.player.is_wrecked() {
+                        self.game_over = true;
+                    }
+                }
+            } else if !boulder.near_missed {
+                let grazing: Vec<Shape> = self.player.shapes()
+                    .into_iter()
+                    .map(|shape| shape.inflated(boulder_tuning.near_miss_margin))
+                    .collect();
+                let grazed = collision::sweep_shapes(
+                    &grazing,
+                    self.player.motion(),
+                    &boulder.shapes(),
+                    boulder.motion()
+                );
+                if grazed.is_some() {
+                    boulder.near_missed = true;
+                    self.score += boulder_tuning.near_miss_bonus;
+                }
+            }
+        }
+
+        let powerups = &self.tuning.powerups;
+        let magnet = self.player.effects.is_active(PowerUp::Magnet);
+        let truck_center = self.player.pos - Vec2::new(0.0, self.player.height / 2.0);
+        for pickup in &mut self.pickups {
+            pickup.update(self.speed_x);
+            if magnet && !pickup.collected && pickup.distance_to(truck_center) < powerups.magnet_radius {
+                pickup.pull_towards(truck_center, powerups.magnet_pull);
+            }
+
+            // Check if pickup is off screen
+            if pickup.pos.x + pickup.width < 0.0 {
+                pickup.reposition(PLAYFIELD_WIDTH, self.camera.top(), PLAYFIELD_HEIGHT, &mut self.rng);
+                // Coins sometimes come back as power-ups, and power-ups as coins
+                if matches!(pickup.pickup_type, PickupType::Coin | PickupType::PowerUp(_)) {
+                    let next = if self.rng.gen_f32() < powerups.spawn_chance {
+                        PowerUp::choose(powerups, &mut self.rng).map_or(PickupType::Coin, PickupType::PowerUp)
+                    } else {
+                        PickupType::Coin
+                    };
+                    pickup.set_type(next, &self.tuning.pickups);
+                } else if matches!(pickup.pickup_type, PickupType::Gem | PickupType::FuelCan | PickupType::InkPot) {
+                    // Gems, fuel cans and ink pots share spawns the same way
+                    let roll = self.rng.gen_f32();
+                    let next = if roll < self.tuning.fuel.can_chance {
+                        PickupType::FuelCan
+                    } else if roll < self.tuning.fuel.can_chance + self.tuning.sculpting.pot_chance {
+                        PickupType::InkPot
+                    } else {
+                        PickupType::Gem
+                    };
+                    pickup.set_type(next, &self.tuning.pickups);
+                }
+            }
+
+            // Check collision with player
+            if collision::sweep(&self.player, pickup).is_some() {
+                pickup.collected = true;
+                match pickup.pickup_type {
+                    PickupType::PowerUp(power_up) => self.player.effects.activate(power_up, powerups),
+                    PickupType::FuelCan => self.player.refuel(self.tuning.fuel.can_amount, self.tuning.fuel.capacity),
+                    PickupType::InkPot => {
+                        self.ink = (self.ink + self.tuning.sculpting.pot_amount).min(self.tuning.sculpting.capacity);
+                    },
+                    _ => {
+                        self.score += pickup.value;
+                        self.player.velocity.x += self.tuning.pickups.speed_bonus / dt;
+                    },
+                }
+            }
+        }
+    }
+
+    /// Adds this step's scrolling to the distance and pays out for any new
+    /// best altitude. Only ground the wheels are on counts, so sculpting a
+    /// peak under a jumping truck doesn't score.
+    fn track_progress(&mut self) {
+        let stats = &mut self.stats;
+        stats.distance += self.speed_x;
+        stats.altitude = START_GROUND - self.terrain.height_at(self.player.pos.x);
+
+        if self.player.on_ground && stats.altitude > stats.best_altitude {
+            let scoring = &self.tuning.scoring;
+            let metres_climbed = scoring.metres(stats.altitude) - scoring.metres(stats.best_altitude);
+            self.score += metres_climbed * scoring.climb_points;
+            stats.best_altitude = stats.altitude;
+        }
+    }
+
+    /// Draws the scenery that stays visible behind menus and the game-over screen.
+    ///
+    /// `screen` is the canvas' screen coordinates, which are restored
+    /// afterwards. `alpha` is how far the renderer is between the previous
+    /// and the current step, in `0.0..=1.0`; positions are interpolated by it.
+    pub fn draw_scenery(&self, ctx: &mut Context, canvas: &mut Canvas, screen: Rect, alpha: f32) -> GameResult {
+        self.background.draw(ctx, canvas, alpha)?;
+
+        let view = self.camera.view(screen, alpha);
+        canvas.set_screen_coordinates(view);
+        self.terrain.draw(ctx, canvas, view.bottom(), alpha)?;
+        canvas.set_screen_coordinates(screen);
+        Ok(())
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, screen: Rect, alpha: f32) -> GameResult {
+        self.draw_scenery(ctx, canvas, screen, alpha)?;
+
+        // Everything else is in world coordinates too
+        canvas.set_screen_coordinates(self.camera.view(screen, alpha));
+
+        for boulder in &self.boulders {
+            boulder.draw(ctx, canvas, alpha)?;
+        }
+
+        for pickup in &self.pickups {
+            pickup.draw(ctx, canvas, alpha)?;
+        }
+
+        // Only draw player if not collided
+        if !self.game_over {
+            self.player.draw(ctx, canvas, alpha)?;
+        }
+
+        canvas.set_screen_coordinates(screen);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scripted run: driving, jumping now and then and sweeping the mouse
+    /// across the screen.
+    fn input_at(tick: usize) -> FrameInput {
+        let sweep = (tick % 120) as f32 / 120.0;
+        FrameInput {
+            mouse_pos: Vec2::new(sweep * PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT * (0.5 + 0.3 * sweep)),
+            jump: tick % 90 == 45,
+            throttle: tick % 200 < 150,
+            brake: tick % 200 >= 190,
+            speed_up: tick % 300 == 100,
+            slow_down: tick % 300 == 250,
+            brush: BrushMode::default(),
+        }
+    }
+
+    fn run(seed: u64, style: TerrainStyle, ticks: usize) -> World {
+        let mut world = World::new(seed, style, &Tuning::default());
+        for tick in 0..ticks {
+            world.step(&input_at(tick), TICK_DT);
+        }
+        world
+    }
+
+    fn assert_same_state(a: &World, b: &World) {
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.player.pos, b.player.pos);
+        assert_eq!(a.player.velocity, b.player.velocity);
+        assert_eq!(a.stats, b.stats);
+        assert_eq!(a.ink, b.ink);
+        assert_eq!(a.game_over, b.game_over);
+        let boulders = |w: &World| w.boulders.iter().map(|b| b.pos).collect::<Vec<_>>();
+        assert_eq!(boulders(a), boulders(b));
+        let pickups = |w: &World| w.pickups.iter().map(|p| (p.pos, p.pickup_type)).collect::<Vec<_>>();
+        assert_eq!(pickups(a), pickups(b));
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_run() {
+        for style in TerrainStyle::ALL {
+            for seed in [0, 1, 42, u64::MAX] {
+                assert_same_state(&run(seed, style, 600), &run(seed, style, 600));
+            }
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_runs() {
+        let a = World::new(1, TerrainStyle::Blocky, &Tuning::default());
+        let b = World::new(2, TerrainStyle::Blocky, &Tuning::default());
+        let boulders = |w: &World| w.boulders.iter().map(|b| b.pos).collect::<Vec<_>>();
+        assert_ne!(boulders(&a), boulders(&b));
+    }
+
+    #[test]
+    fn the_truck_stays_at_the_same_x() {
+        for seed in 0..30 {
+            let mut world = World::new(seed, TerrainStyle::Blocky, &Tuning::default());
+            let start = world.player.pos.x;
+            let mut rng = GameRng::new(seed + 1000);
+            for _ in 0..1200 {
+                let input = FrameInput {
+                    mouse_pos: Vec2::new(rng.gen_f32() * PLAYFIELD_WIDTH, rng.gen_f32() * PLAYFIELD_HEIGHT),
+                    jump: rng.gen_f32() < 0.05,
+                    throttle: rng.gen_f32() < 0.7,
+                    brake: rng.gen_f32() < 0.1,
+                    speed_up: rng.gen_f32() < 0.02,
+                    slow_down: rng.gen_f32() < 0.02,
+                    brush: BrushMode::default(),
+                };
+                world.step(&input, TICK_DT);
+                assert_eq!(world.player.pos.x, start, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn slow_motion_burns_fuel_for_the_distance_covered() {
+        let tuning = Tuning::default();
+        let burnt = |slow: bool| {
+            let mut world = World::new(3, TerrainStyle::Blocky, &tuning);
+            // Settle on the flat start first
+            for _ in 0..60 {
+                world.step(&FrameInput::default(), TICK_DT);
+            }
+            if slow {
+                world.player.effects.activate(PowerUp::SlowMotion, &tuning.powerups);
+            }
+            let (fuel, distance) = (world.player.fuel, world.stats.distance);
+            for _ in 0..60 {
+                world.step(&FrameInput::default(), TICK_DT);
+            }
+            (fuel - world.player.fuel, world.stats.distance - distance)
+        };
+        let (normal_fuel, normal_distance) = burnt(false);
+        let (slow_fuel, slow_distance) = burnt(true);
+        assert!(slow_distance < normal_distance * 0.6);
+        let per_pixel = |fuel: f32, distance: f32| fuel / distance;
+        assert!((per_pixel(slow_fuel, slow_distance) - per_pixel(normal_fuel, normal_distance)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn runs_make_progress_without_input() {
+        let mut world = World::new(7, TerrainStyle::Blocky, &Tuning::default());
+        let start = world.player.pos;
+        for _ in 0..60 {
+            world.step(&FrameInput::default(), TICK_DT);
+        }
+        assert!(world.stats.distance > 0.0);
+        assert_eq!(world.player.pos.x, start.x);
+        // The drop onto the starting ground is a gentle one
+        assert_eq!(world.player.health_fraction(), 1.0);
+    }
+
+    #[test]
+    fn the_speed_keys_nudge_the_scroll_speed() {
+        let tuning = Tuning::default();
+        let step = tuning.physics.manual_speed_step;
+        let mut world = World::new(7, TerrainStyle::Blocky, &tuning);
+        let press = |speed_up, slow_down| FrameInput { speed_up, slow_down, ..FrameInput::default() };
+
+        world.step(&FrameInput::default(), TICK_DT);
+        let before = world.speed_x;
+        world.step(&press(true, false), TICK_DT);
+        assert!((world.speed_x - before - step).abs() < step * 0.1);
+
+        // Slowing down never runs the world backwards
+        for _ in 0..200 {
+            world.step(&press(false, true), TICK_DT);
+        }
+        assert_eq!(world.speed_x, 0.0);
+    }
+
+    #[test]
+    fn the_truck_below_a_lagging_view_is_still_in_the_run() {
+        let mut world = World::new(9, TerrainStyle::Blocky, &Tuning::default());
+        // As if the view were still easing down after a fast drop
+        world.camera = Camera::new(-PLAYFIELD_HEIGHT);
+        for _ in 0..60 {
+            world.step(&FrameInput::default(), TICK_DT);
+            assert!(!world.game_over);
+            let on_screen = world.player.pos.y - world.camera.top();
+            assert!(on_screen <= PLAYFIELD_HEIGHT, "{}", on_screen);
+        }
+    }
+}